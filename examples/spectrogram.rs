@@ -0,0 +1,93 @@
+//! Scrolls a spectrogram by one column per frame with a [`StreamingTexture`].
+//!
+//! This also serves as the benchmark of partial texture updates: every second it prints
+//! the average number of bytes uploaded per frame, next to what uploading the whole image
+//! every frame would cost.
+
+use baseview::{Size, WindowOpenOptions, WindowScalePolicy};
+use egui::{Color32, Context};
+use egui_baseview::{EguiWindow, Queue, StreamingTexture};
+
+const WIDTH: usize = 512;
+const HEIGHT: usize = 256;
+/// The number of frames the printed average is taken over.
+const REPORT_FRAMES: usize = 60;
+
+struct State {
+    texture: Option<StreamingTexture>,
+    phase: f32,
+    frames: usize,
+    uploaded_bytes: usize,
+}
+
+fn main() {
+    let settings = WindowOpenOptions {
+        title: String::from("egui-baseview spectrogram"),
+        size: Size::new(WIDTH as f64, HEIGHT as f64 + 40.0),
+        scale: WindowScalePolicy::SystemScaleFactor,
+        gl_config: Some(Default::default()),
+    };
+
+    let state = State {
+        texture: None,
+        phase: 0.0,
+        frames: 0,
+        uploaded_bytes: 0,
+    };
+
+    EguiWindow::open_blocking(
+        settings,
+        state,
+        |_egui_ctx: &Context, queue: &mut Queue, state: &mut State| {
+            state.texture = Some(StreamingTexture::new(
+                queue,
+                [WIDTH, HEIGHT],
+                Color32::BLACK,
+                egui::TextureFilter::Nearest,
+            ));
+        },
+        |egui_ctx: &Context, queue: &mut Queue, state: &mut State| {
+            state.frames += 1;
+            state.uploaded_bytes += queue.frame_stats().texture_upload_bytes;
+            if state.frames == REPORT_FRAMES {
+                println!(
+                    "uploaded {} bytes per frame on average, the whole image is {} bytes",
+                    state.uploaded_bytes / state.frames,
+                    WIDTH * HEIGHT * 4
+                );
+                state.frames = 0;
+                state.uploaded_bytes = 0;
+            }
+
+            let texture = state.texture.as_mut().unwrap();
+
+            // A fake spectrum with a peak that moves up and down.
+            state.phase += 0.05;
+            let peak = (state.phase.sin() * 0.4 + 0.5) * HEIGHT as f32;
+            let column = (0..HEIGHT)
+                .map(|y| {
+                    let level = (1.0 - (y as f32 - peak).abs() / 32.0).max(0.0);
+                    Color32::from_gray((level * 255.0) as u8)
+                })
+                .collect();
+            texture.push_column(queue, column);
+
+            egui::CentralPanel::default().show(egui_ctx, |ui| {
+                // The uploaded bytes of the previous frame: one column instead of the
+                // whole image.
+                ui.label(format!(
+                    "uploaded: {} bytes per frame",
+                    queue.frame_stats().texture_upload_bytes
+                ));
+
+                let (rect, _) = ui.allocate_exact_size(
+                    egui::vec2(WIDTH as f32, HEIGHT as f32),
+                    egui::Sense::hover(),
+                );
+                texture.paint(ui.painter(), rect);
+            });
+
+            egui_ctx.request_repaint();
+        },
+    );
+}
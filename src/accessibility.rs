@@ -0,0 +1,244 @@
+//! The accessibility tree egui produces with the `accesskit` feature.
+//!
+//! egui describes its widgets as an [AccessKit](https://accesskit.dev) tree, which
+//! [`crate::EguiWindow`] keeps up to date from the output of every frame. It can be
+//! inspected with [`crate::Queue::accessibility_tree`], e.g. to test that a UI is
+//! accessible.
+//!
+//! To expose the tree to screen readers, set an [`AccessibilityAdapter`] with
+//! [`crate::Queue::set_accessibility_adapter`]. The native AccessKit adapters need to
+//! handle window messages (e.g. `WM_GETOBJECT` on Windows) that baseview doesn't give us
+//! access to, so the adapter is attached through the window's raw handle, and it sends the
+//! action requests of assistive technologies back to the window as
+//! [`crate::ExternalInput::AccessKitAction`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use egui::accesskit::{Node, NodeId, Tree, TreeUpdate};
+use raw_window_handle::HasRawWindowHandle;
+
+use crate::InputSender;
+
+/// Exposes a window's accessibility tree to assistive technologies, e.g. a native
+/// AccessKit adapter.
+pub trait AccessibilityAdapter: Send + 'static {
+    /// Attach the adapter to `window`, starting with the current `tree`. Action requests
+    /// are sent to `actions` as [`crate::ExternalInput::AccessKitAction`].
+    fn attach(&mut self, window: &dyn HasRawWindowHandle, tree: &AccessibilityTree, actions: InputSender);
+
+    /// Apply an update of the tree.
+    fn update(&mut self, update: TreeUpdate);
+}
+
+/// The accessibility state of a window.
+#[derive(Default)]
+pub(crate) struct WindowAccessibility {
+    pub tree: AccessibilityTree,
+    adapter: Option<Box<dyn AccessibilityAdapter>>,
+    /// Whether `adapter` was attached to the window.
+    attached: bool,
+}
+
+impl WindowAccessibility {
+    /// Set the adapter, it is attached with [`WindowAccessibility::attach_adapter`].
+    pub fn set_adapter(&mut self, adapter: Box<dyn AccessibilityAdapter>) {
+        self.adapter = Some(adapter);
+        self.attached = false;
+    }
+
+    /// Attach the adapter to the window, if it isn't attached yet.
+    pub fn attach_adapter(&mut self, window: &dyn HasRawWindowHandle, actions: &InputSender) {
+        if let Some(adapter) = &mut self.adapter {
+            if !self.attached {
+                adapter.attach(window, &self.tree, actions.clone());
+                self.attached = true;
+            }
+        }
+    }
+
+    /// Apply an update of egui's tree and pass it on to the adapter.
+    pub fn update(&mut self, update: TreeUpdate) {
+        if let Some(adapter) = &mut self.adapter {
+            if self.attached {
+                adapter.update(update.clone());
+            }
+        }
+        self.tree.update(update);
+    }
+}
+
+/// The current state of an accessibility tree, built from incremental [`TreeUpdate`]s.
+#[derive(Clone, Debug, Default)]
+pub struct AccessibilityTree {
+    nodes: HashMap<NodeId, Arc<Node>>,
+    root: Option<NodeId>,
+    focus: Option<NodeId>,
+}
+
+impl AccessibilityTree {
+    /// Apply an update, removing the nodes that are no longer reachable from the root.
+    pub fn update(&mut self, update: TreeUpdate) {
+        self.nodes.extend(update.nodes);
+        if let Some(tree) = update.tree {
+            self.root = Some(tree.root);
+        }
+        self.focus = update.focus;
+
+        let mut reachable = HashMap::with_capacity(self.nodes.len());
+        let mut stack: Vec<NodeId> = self.root.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes.remove(&id) {
+                stack.extend(node.children.iter().copied());
+                reachable.insert(id, node);
+            }
+        }
+        self.nodes = reachable;
+    }
+
+    /// The root node, `None` before the first frame.
+    pub fn root(&self) -> Option<(NodeId, &Node)> {
+        let id = self.root?;
+        Some((id, self.node(id)?))
+    }
+
+    /// The node that has the keyboard focus, if any.
+    pub fn focus(&self) -> Option<(NodeId, &Node)> {
+        let id = self.focus?;
+        Some((id, self.node(id)?))
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(&id).map(|node| node.as_ref())
+    }
+
+    /// All nodes of the tree, in no particular order.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().map(|(id, node)| (*id, node.as_ref()))
+    }
+
+    /// An update that describes the whole tree, e.g. to initialize an adapter. `None`
+    /// before the first frame.
+    pub fn to_update(&self) -> Option<TreeUpdate> {
+        Some(TreeUpdate {
+            nodes: self
+                .nodes
+                .iter()
+                .map(|(id, node)| (*id, node.clone()))
+                .collect(),
+            tree: Some(Tree::new(self.root?)),
+            focus: self.focus,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::accesskit::Role;
+    use std::num::NonZeroU128;
+    use std::sync::Mutex;
+
+    fn id(id: u128) -> NodeId {
+        NodeId(NonZeroU128::new(id).unwrap())
+    }
+
+    fn node(role: Role, children: Vec<NodeId>) -> Arc<Node> {
+        Arc::new(Node {
+            role,
+            children,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let mut tree = AccessibilityTree::default();
+        assert!(tree.root().is_none());
+
+        tree.update(TreeUpdate {
+            nodes: vec![
+                (id(1), node(Role::Window, vec![id(2), id(3)])),
+                (id(2), node(Role::Button, vec![])),
+                (id(3), node(Role::Button, vec![])),
+            ],
+            tree: Some(Tree::new(id(1))),
+            focus: Some(id(2)),
+        });
+
+        assert_eq!(tree.root().unwrap().0, id(1));
+        assert_eq!(tree.focus().unwrap().0, id(2));
+        assert_eq!(tree.nodes().count(), 3);
+
+        // Only the changed nodes are sent, the removed button is pruned.
+        tree.update(TreeUpdate {
+            nodes: vec![(id(1), node(Role::Window, vec![id(2)]))],
+            tree: None,
+            focus: None,
+        });
+
+        assert_eq!(tree.root().unwrap().0, id(1));
+        assert!(tree.focus().is_none());
+        assert!(tree.node(id(2)).is_some());
+        assert!(tree.node(id(3)).is_none());
+    }
+
+    #[test]
+    fn adapter_starts_with_the_current_tree() {
+        struct NoWindow;
+
+        unsafe impl HasRawWindowHandle for NoWindow {
+            fn raw_window_handle(&self) -> raw_window_handle::RawWindowHandle {
+                unreachable!("the test adapter doesn't use the window")
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct TestAdapter(Arc<Mutex<Vec<TreeUpdate>>>);
+
+        impl AccessibilityAdapter for TestAdapter {
+            fn attach(
+                &mut self,
+                _window: &dyn HasRawWindowHandle,
+                tree: &AccessibilityTree,
+                _actions: InputSender,
+            ) {
+                self.0.lock().unwrap().extend(tree.to_update());
+            }
+
+            fn update(&mut self, update: TreeUpdate) {
+                self.0.lock().unwrap().push(update);
+            }
+        }
+
+        let adapter = TestAdapter::default();
+        let mut accessibility = WindowAccessibility::default();
+        accessibility.set_adapter(Box::new(adapter.clone()));
+        accessibility.update(TreeUpdate {
+            nodes: vec![
+                (id(1), node(Role::Window, vec![id(2)])),
+                (id(2), node(Role::Button, vec![])),
+            ],
+            tree: Some(Tree::new(id(1))),
+            focus: None,
+        });
+        assert!(adapter.0.lock().unwrap().is_empty());
+
+        accessibility.attach_adapter(&NoWindow, &InputSender::default());
+        accessibility.attach_adapter(&NoWindow, &InputSender::default());
+        {
+            let updates = adapter.0.lock().unwrap();
+            assert_eq!(updates.len(), 1);
+            assert_eq!(updates[0].nodes.len(), 2);
+            assert_eq!(updates[0].tree.as_ref().unwrap().root, id(1));
+        }
+
+        accessibility.update(TreeUpdate {
+            nodes: vec![],
+            tree: None,
+            focus: Some(id(2)),
+        });
+        assert_eq!(adapter.0.lock().unwrap()[1].focus, Some(id(2)));
+        assert_eq!(accessibility.tree.focus().unwrap().0, id(2));
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The source of time for egui's input time, animations and repaint scheduling.
+///
+/// Frame timings in [`crate::FrameStats`] always use the system clock.
+pub trait Clock: Send + 'static {
+    fn now(&self) -> Instant;
+}
+
+/// The system clock, this is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when it is advanced, for tests and offline rendering.
+///
+/// Clones share the same time, so a clone can be kept to advance the clock after it
+/// was handed to the window.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed_nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed_nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::AcqRel);
+    }
+
+    /// The time that passed since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::Acquire))
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{input_time, schedule_repaint};
+    use crate::RepaintReason;
+
+    #[test]
+    fn manual_clock_drives_input_time_and_repaints() {
+        let clock = ManualClock::new();
+        // The window owns the clock, the test keeps a clone to advance it.
+        let window_clock: Box<dyn Clock> = Box::new(clock.clone());
+        let start = window_clock.now();
+        let mut deadline = None;
+
+        // egui asks for a repaint in 250 ms.
+        let repaint_after = Duration::from_millis(250);
+        assert_eq!(
+            schedule_repaint(&mut deadline, window_clock.now(), false, repaint_after),
+            None
+        );
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(input_time(window_clock.as_ref(), start), 0.1);
+        assert_eq!(
+            schedule_repaint(&mut deadline, window_clock.now(), false, Duration::MAX),
+            None
+        );
+
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(input_time(window_clock.as_ref(), start), 0.25);
+        assert_eq!(
+            schedule_repaint(&mut deadline, window_clock.now(), false, Duration::MAX),
+            Some(RepaintReason::Scheduled)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::FrameStats;
+
+/// How many of the most recent input events are shown.
+const MAX_EVENTS: usize = 16;
+
+/// A key together with the modifiers that have to be held down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyChord {
+    pub modifiers: egui::Modifiers,
+    pub key: egui::Key,
+}
+
+impl KeyChord {
+    pub fn new(modifiers: egui::Modifiers, key: egui::Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    fn matches(&self, event: &egui::Event) -> bool {
+        match event {
+            egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+            } => {
+                *key == self.key
+                    && modifiers.alt == self.modifiers.alt
+                    && modifiers.shift == self.modifiers.shift
+                    && modifiers.command == self.modifiers.command
+            }
+            _ => false,
+        }
+    }
+
+    fn is_release(&self, event: &egui::Event) -> bool {
+        matches!(
+            event,
+            egui::Event::Key { key, pressed: false, .. } if *key == self.key
+        )
+    }
+}
+
+/// An overlay showing the frame rate, frame timings and the translated input events.
+#[derive(Default)]
+pub(crate) struct DebugOverlay {
+    pub visible: bool,
+    pub toggle_chord: Option<KeyChord>,
+    /// Whether the key of the chord is held down after toggling the overlay.
+    chord_down: bool,
+    events: VecDeque<egui::Event>,
+    /// The start times of the rendered frames during the last second.
+    rendered_frames: VecDeque<Instant>,
+}
+
+impl DebugOverlay {
+    /// Record the input events of a frame and toggle the overlay if the chord was pressed.
+    ///
+    /// The chord's key press and release are removed from `events`, so they don't reach
+    /// egui or `update`.
+    pub fn record_input(&mut self, events: &mut Vec<egui::Event>) {
+        if self.toggle_chord.is_none() && !self.visible {
+            return;
+        }
+
+        events.retain(|event| {
+            if self.toggle_chord.is_some_and(|chord| chord.matches(event)) {
+                self.visible = !self.visible;
+                self.chord_down = true;
+                return false;
+            }
+
+            if self.chord_down && self.toggle_chord.is_some_and(|chord| chord.is_release(event)) {
+                self.chord_down = false;
+                return false;
+            }
+
+            if self.events.len() == MAX_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(event.clone());
+            true
+        });
+    }
+
+    /// Record a finished frame.
+    pub fn record_frame(&mut self, frame_start: Instant, frame_stats: &FrameStats) {
+        if frame_stats.repaint_reason.is_some() {
+            self.rendered_frames.push_back(frame_start);
+        }
+
+        while let Some(t) = self.rendered_frames.front() {
+            if frame_start.duration_since(*t) > Duration::from_secs(1) {
+                self.rendered_frames.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn show(&self, egui_ctx: &egui::Context, frame_stats: &FrameStats) {
+        if !self.visible {
+            return;
+        }
+
+        let num_textures = egui_ctx.tex_manager().read().num_allocated();
+        let modifiers = egui_ctx.input().modifiers;
+
+        egui::Area::new("egui_baseview_debug_overlay")
+            .order(egui::Order::Debug)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+            .interactable(false)
+            .show(egui_ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.monospace(format!("FPS: {}", self.rendered_frames.len()));
+                    ui.monospace(format!(
+                        "frame: {:.2} ms (update {:.2} ms)",
+                        frame_stats.total.as_secs_f64() * 1e3,
+                        frame_stats.user_update.as_secs_f64() * 1e3,
+                    ));
+                    ui.monospace(format!("repaint reason: {:?}", frame_stats.repaint_reason));
+                    ui.monospace(format!(
+                        "textures: {} ({} KiB uploaded)",
+                        num_textures,
+                        frame_stats.texture_upload_bytes / 1024
+                    ));
+                    ui.monospace(format!("modifiers: {:?}", modifiers));
+                    ui.separator();
+                    for event in self.events.iter().rev() {
+                        ui.monospace(format!("{:?}", event));
+                    }
+                });
+            });
+
+        // Keep the frame rate up to date.
+        egui_ctx.request_repaint_after(Duration::from_millis(500));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: egui::Key, pressed: bool, modifiers: egui::Modifiers) -> egui::Event {
+        egui::Event::Key {
+            key,
+            pressed,
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn chord_toggles_the_overlay_and_is_consumed() {
+        let mut overlay = DebugOverlay {
+            toggle_chord: Some(KeyChord::new(egui::Modifiers::COMMAND, egui::Key::D)),
+            ..Default::default()
+        };
+
+        let mut events = vec![
+            key(egui::Key::D, true, egui::Modifiers::COMMAND),
+            key(egui::Key::A, true, egui::Modifiers::NONE),
+            key(egui::Key::D, false, egui::Modifiers::COMMAND),
+        ];
+        overlay.record_input(&mut events);
+
+        assert!(overlay.visible);
+        assert_eq!(events, vec![key(egui::Key::A, true, egui::Modifiers::NONE)]);
+
+        // A `D` without the modifiers is passed on.
+        let mut events = vec![key(egui::Key::D, true, egui::Modifiers::NONE)];
+        overlay.record_input(&mut events);
+
+        assert!(overlay.visible);
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -0,0 +1,41 @@
+use std::io;
+use std::path::PathBuf;
+
+use raw_window_handle::HasRawWindowHandle;
+
+/// The data of a drag started with [`crate::Queue::start_drag`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DragPayload {
+    /// Files, e.g. an audio file rendered by the application.
+    pub files: Vec<PathBuf>,
+    /// Data in other formats as `(MIME type, bytes)`, like the `copied_data` of
+    /// `egui::PlatformOutput`.
+    pub data: Vec<(String, Vec<u8>)>,
+}
+
+impl DragPayload {
+    /// A payload of files.
+    pub fn files(files: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            data: Vec::new(),
+        }
+    }
+
+    /// Add data in another format.
+    pub fn with_data(mut self, mime: impl Into<String>, data: Vec<u8>) -> Self {
+        self.data.push((mime.into(), data));
+        self
+    }
+}
+
+/// Starts the OS drag sessions for [`crate::Queue::start_drag`].
+///
+/// baseview can receive drops but can't start a drag, so the platform part is provided
+/// by the application, e.g. with `DoDragDrop` on Windows, a dragging session of the
+/// window's `NSView` on macOS or XDND on X11.
+pub trait DragSource: Send + 'static {
+    /// Start dragging `payload` out of `window`. This is called after the `update` in
+    /// which the drag was started, while the mouse button is still pressed.
+    fn start_drag(&mut self, window: &dyn HasRawWindowHandle, payload: DragPayload) -> io::Result<()>;
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use egui::{FontData, FontDefinitions, FontFamily};
+
+/// Font files loaded with [`FontConfig::load_file_cached`], shared by every window in the
+/// process. The data is never freed, because egui needs it for as long as any window uses
+/// it and plugin instances come and go.
+static FONT_CACHE: Mutex<BTreeMap<PathBuf, &'static [u8]>> = Mutex::new(BTreeMap::new());
+
+/// The fonts of a window, built on top of [`egui::FontDefinitions`].
+///
+/// Fonts are added under a name and then used by font families. Each family is a chain of
+/// fonts that are tried in order, so a glyph that is missing in the first font (e.g. a CJK
+/// character) is taken from the next one that has it, see
+/// [`FontConfig::add_script_fallback`]. Pass the config to the window with
+/// [`crate::Queue::set_font_config`] in `build`, so it is in place before the first frame.
+///
+/// Only the font files are shared between windows, see [`FontConfig::load_file_cached`].
+/// The font atlas is not: egui rasterises the glyphs into an atlas per `egui::Context`, so
+/// every window still builds its own. Its size depends on the glyphs the window has drawn,
+/// not on the size of the font files.
+#[derive(Clone, Debug, Default)]
+pub struct FontConfig {
+    definitions: FontDefinitions,
+}
+
+impl FontConfig {
+    /// Start with egui's default fonts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start without any fonts.
+    pub fn empty() -> Self {
+        Self {
+            definitions: FontDefinitions::empty(),
+        }
+    }
+
+    /// Add a font from data that lives for the whole program, e.g. from `include_bytes!`.
+    pub fn add_font_bytes(&mut self, name: impl Into<String>, bytes: &'static [u8]) {
+        self.add_font(name, FontData::from_static(bytes));
+    }
+
+    /// Add a font file. The file is read for every call; see
+    /// [`FontConfig::load_file_cached`] to share it between windows.
+    pub fn load_file(&mut self, name: impl Into<String>, path: impl AsRef<Path>) -> io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        self.add_font(name, FontData::from_owned(bytes));
+        Ok(())
+    }
+
+    /// Add a font file. The file is only read once per process and its data is shared by
+    /// every window that loads it.
+    pub fn load_file_cached(
+        &mut self,
+        name: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+
+        let mut cache = FONT_CACHE.lock().unwrap_or_else(|err| err.into_inner());
+        let bytes = match cache.get(path) {
+            Some(bytes) => *bytes,
+            None => {
+                let bytes: &'static [u8] = Box::leak(std::fs::read(path)?.into_boxed_slice());
+                cache.insert(path.to_owned(), bytes);
+                bytes
+            }
+        };
+
+        self.add_font_bytes(name, bytes);
+        Ok(())
+    }
+
+    /// Add a font, replacing a font with the same name.
+    pub fn add_font(&mut self, name: impl Into<String>, data: FontData) {
+        self.definitions.font_data.insert(name.into(), data);
+    }
+
+    /// Make a font the first choice of a family.
+    pub fn set_primary(&mut self, family: FontFamily, name: impl Into<String>) {
+        self.fonts_mut(family).insert(0, name.into());
+    }
+
+    /// Add a font to the end of the chain of a family, so it is used for the glyphs the
+    /// other fonts of the family don't have.
+    pub fn push_fallback(&mut self, family: FontFamily, name: impl Into<String>) {
+        self.fonts_mut(family).push(name.into());
+    }
+
+    /// Use a font for the scripts the other fonts don't cover, e.g. CJK or Arabic, by adding
+    /// it to the end of the chain of every family. Fonts for several scripts can be added,
+    /// they are tried in the order they were added.
+    pub fn add_script_fallback(&mut self, name: impl Into<String>) {
+        let name = name.into();
+
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            self.fonts_mut(family);
+        }
+        for names in self.definitions.families.values_mut() {
+            if !names.contains(&name) {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    /// Replace the whole chain of a family.
+    pub fn set_family(&mut self, family: FontFamily, names: Vec<String>) {
+        *self.fonts_mut(family) = names;
+    }
+
+    /// The names of the fonts of a family, in the order they are tried.
+    pub fn family(&self, family: &FontFamily) -> &[String] {
+        self.definitions
+            .families
+            .get(family)
+            .map_or(&[], |names| names.as_slice())
+    }
+
+    pub fn definitions(&self) -> &FontDefinitions {
+        &self.definitions
+    }
+
+    pub fn into_definitions(self) -> FontDefinitions {
+        self.definitions
+    }
+
+    /// Use the fonts in the given context, starting with the next frame. In a window, use
+    /// [`crate::Queue::set_font_config`] instead.
+    pub fn apply(&self, egui_ctx: &egui::Context) {
+        egui_ctx.set_fonts(self.definitions.clone());
+    }
+
+    fn fonts_mut(&mut self, family: FontFamily) -> &mut Vec<String> {
+        self.definitions.families.entry(family).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_chain_order() {
+        let mut config = FontConfig::empty();
+        config.add_font_bytes("latin", &[]);
+        config.add_font_bytes("cjk", &[]);
+        config.add_font_bytes("display", &[]);
+
+        config.push_fallback(FontFamily::Proportional, "latin");
+        config.push_fallback(FontFamily::Proportional, "cjk");
+        config.set_primary(FontFamily::Proportional, "display");
+
+        assert_eq!(
+            config.family(&FontFamily::Proportional),
+            ["display", "latin", "cjk"]
+        );
+        assert!(config.family(&FontFamily::Monospace).is_empty());
+    }
+
+    #[test]
+    fn script_fallbacks_are_added_to_every_family() {
+        let mut config = FontConfig::empty();
+        config.add_font_bytes("latin", &[]);
+        config.add_font_bytes("cjk", &[]);
+        config.add_font_bytes("arabic", &[]);
+
+        config.push_fallback(FontFamily::Proportional, "latin");
+        config.set_family(FontFamily::Name("heading".into()), vec!["latin".into()]);
+        config.add_script_fallback("cjk");
+        config.add_script_fallback("arabic");
+        config.add_script_fallback("cjk");
+
+        assert_eq!(
+            config.family(&FontFamily::Proportional),
+            ["latin", "cjk", "arabic"]
+        );
+        assert_eq!(config.family(&FontFamily::Monospace), ["cjk", "arabic"]);
+        assert_eq!(
+            config.family(&FontFamily::Name("heading".into())),
+            ["latin", "cjk", "arabic"]
+        );
+    }
+
+    #[test]
+    fn cached_files_are_read_once() {
+        let path = std::env::temp_dir().join(format!(
+            "egui-baseview-font-cache-{}.ttf",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"font data").unwrap();
+
+        let mut first = FontConfig::empty();
+        first.load_file_cached("font", &path).unwrap();
+
+        // The cached data is used even though the file is gone.
+        std::fs::remove_file(&path).unwrap();
+        let mut second = FontConfig::empty();
+        second.load_file_cached("font", &path).unwrap();
+
+        assert_eq!(
+            first.definitions().font_data["font"].font.as_ptr(),
+            second.definitions().font_data["font"].font.as_ptr()
+        );
+        assert!(FontConfig::empty().load_file("font", &path).is_err());
+    }
+}
@@ -0,0 +1,1466 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{pos2, ClipboardData, ClipboardMime, Pos2};
+use keyboard_types::Modifiers;
+
+/// Where pasted content is read from.
+pub trait ClipboardSource {
+    /// The text on the clipboard, if any.
+    fn text(&mut self) -> Option<String>;
+
+    /// The data of the given MIME type on the clipboard, if any.
+    fn mime_data(&mut self, mime: &str) -> Option<Vec<u8>>;
+}
+
+impl ClipboardSource for copypasta::ClipboardContext {
+    fn text(&mut self) -> Option<String> {
+        use copypasta::ClipboardProvider;
+        self.get_contents().ok()
+    }
+
+    fn mime_data(&mut self, mime: &str) -> Option<Vec<u8>> {
+        use copypasta::ClipboardProvider;
+        self.get_mime_contents(mime).ok()
+    }
+}
+
+/// A touch screen or pen event.
+///
+/// baseview does not report touch and pen input yet, so whoever receives them from the OS
+/// has to pass them to the window with an [`InputSender`], or to
+/// [`InputTranslator::on_touch_event`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchEvent {
+    /// Identifies the touch screen or tablet.
+    pub device_id: u64,
+    /// Identifies the finger or pen for as long as it touches the device.
+    pub id: u64,
+    pub phase: egui::TouchPhase,
+    /// The position in logical coordinates.
+    pub position: baseview::Point,
+    /// The pen pressure in `0.0..=1.0`, `None` if the device does not report pressure.
+    pub pressure: Option<f32>,
+}
+
+/// Input that baseview does not report yet, passed to a window with an [`InputSender`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExternalInput {
+    Touch(TouchEvent),
+    /// See [`InputTranslator::on_momentum_scroll`].
+    MomentumScroll(baseview::ScrollDelta),
+    /// See [`InputTranslator::on_magnify`].
+    Magnify(f32),
+    /// The window lost the pointer capture, e.g. to a dialog of the host, see
+    /// [`InputTranslator::on_pointer_capture_lost`].
+    PointerCaptureLost,
+    /// An action requested by an assistive technology, e.g. through a
+    /// [`crate::accessibility::AccessibilityAdapter`].
+    #[cfg(feature = "accesskit")]
+    AccessKitAction(egui::accesskit::ActionRequest),
+}
+
+/// Feeds [`ExternalInput`] into a window, e.g. from a platform specific touch handler that
+/// is attached to the window's view. Get one with [`crate::Queue::input_sender`].
+///
+/// Clones send to the same window and can be used from any thread. The input is translated
+/// at the start of the next frame.
+#[derive(Clone, Debug, Default)]
+pub struct InputSender {
+    inputs: Arc<Mutex<Vec<ExternalInput>>>,
+}
+
+impl InputSender {
+    pub fn send(&self, input: ExternalInput) {
+        self.lock().push(input);
+    }
+
+    pub(crate) fn take(&self) -> Vec<ExternalInput> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ExternalInput>> {
+        self.inputs.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// What caused the scrolling in a frame, see [`InputTranslator::scroll_kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollKind {
+    /// A mouse wheel that scrolls in notches.
+    Wheel,
+    /// Precise scrolling, e.g. on a trackpad while the fingers touch it.
+    Smooth,
+    /// The momentum that continues smooth scrolling after the fingers were lifted.
+    Momentum,
+}
+
+/// Translates baseview events into an [`egui::RawInput`].
+///
+/// This has no dependency on a window or a renderer, so it can be used by anyone who
+/// embeds egui in baseview through their own `WindowHandler`:
+///
+/// * Pass every event to [`InputTranslator::on_event`].
+/// * Before each frame, set the time and pass [`InputTranslator::take_raw_input`] to
+/// `egui::Context::begin_frame`.
+pub struct InputTranslator {
+    pub(crate) egui_input: egui::RawInput,
+    mouse_pos: Option<Pos2>,
+    /// Kept separately because `egui_input.pixels_per_point` is taken every frame.
+    pixels_per_point: f32,
+    /// Converts the logical positions of baseview into egui's points, `1.0 / ui_scale`.
+    points_per_logical: f32,
+    keys_down: Vec<egui::Key>,
+    /// The pressed pointer buttons and the last pointer position inside the window while
+    /// they were pressed, so they can be released even after the pointer left the window.
+    buttons_down: Vec<(egui::PointerButton, Pos2)>,
+    /// The touch that is also reported as the primary pointer button, and its last position.
+    pointer_touch: Option<(u64, Pos2)>,
+    /// Whether dropped files are delivered to egui, see [`InputTranslator::set_drop_accepted`].
+    drop_accepted: bool,
+    /// What caused the scrolling since the last frame.
+    scroll_kind: Option<ScrollKind>,
+    /// What caused the scrolling in the frame whose input was taken last.
+    frame_scroll_kind: Option<ScrollKind>,
+}
+
+impl Default for InputTranslator {
+    fn default() -> Self {
+        Self::new(egui::RawInput::default())
+    }
+}
+
+impl InputTranslator {
+    pub fn new(egui_input: egui::RawInput) -> Self {
+        Self {
+            egui_input,
+            mouse_pos: None,
+            pixels_per_point: 1.0,
+            points_per_logical: 1.0,
+            keys_down: Vec::new(),
+            buttons_down: Vec::new(),
+            pointer_touch: None,
+            drop_accepted: true,
+            scroll_kind: None,
+            frame_scroll_kind: None,
+        }
+    }
+
+    /// The input collected since the last frame.
+    pub fn raw_input(&self) -> &egui::RawInput {
+        &self.egui_input
+    }
+
+    pub fn raw_input_mut(&mut self) -> &mut egui::RawInput {
+        &mut self.egui_input
+    }
+
+    /// Take the input collected since the last frame, to be passed to
+    /// `egui::Context::begin_frame`.
+    pub fn take_raw_input(&mut self) -> egui::RawInput {
+        self.frame_scroll_kind = self.scroll_kind.take();
+        self.egui_input.take()
+    }
+
+    /// What caused the scrolling in the frame whose input was taken last, `None` if
+    /// nothing was scrolled. If different kinds of scrolling happened, this is the last one.
+    pub fn scroll_kind(&self) -> Option<ScrollKind> {
+        self.frame_scroll_kind
+    }
+
+    /// Set the scale used to convert pixel based scroll deltas into points.
+    pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+        self.pixels_per_point = pixels_per_point;
+    }
+
+    /// Set the UI scale (`1.0` = 100%) that is applied on top of the system scale factor.
+    ///
+    /// baseview reports positions in logical coordinates, which only match egui's points
+    /// at a UI scale of 100%.
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.points_per_logical = 1.0 / ui_scale;
+    }
+
+    /// Whether the files of a `DragDropped` event are added to `RawInput::dropped_files`.
+    ///
+    /// Set this to the decision that was reported to the OS for the drag, so that rejected
+    /// drops don't reach egui. Defaults to `true`.
+    pub fn set_drop_accepted(&mut self, accepted: bool) {
+        self.drop_accepted = accepted;
+    }
+
+    /// Convert a logical position into points.
+    fn to_points(&self, position: &baseview::Point) -> Pos2 {
+        pos2(
+            position.x as f32 * self.points_per_logical,
+            position.y as f32 * self.points_per_logical,
+        )
+    }
+
+    /// Set the physical size of the window and egui's `pixels_per_point`.
+    pub fn set_screen(&mut self, physical_width: u32, physical_height: u32, pixels_per_point: f32) {
+        self.set_pixels_per_point(pixels_per_point);
+        self.egui_input.pixels_per_point = Some(pixels_per_point);
+        self.egui_input.screen_rect = Some(logical_screen_rect(
+            physical_width,
+            physical_height,
+            pixels_per_point,
+        ));
+    }
+
+    /// Translate a baseview event. Returns `false` if the event was not used.
+    ///
+    /// `clipboard` is read when a paste command is pressed.
+    pub fn on_event(
+        &mut self,
+        event: &baseview::Event,
+        clipboard: Option<&mut dyn ClipboardSource>,
+    ) -> bool {
+        match event {
+            baseview::Event::Mouse(event) => self.on_mouse_event(event),
+            baseview::Event::Keyboard(event) => self.on_keyboard_event(event, clipboard),
+            baseview::Event::Window(baseview::WindowEvent::Resized(window_info)) => {
+                self.set_screen(
+                    window_info.physical_size().width,
+                    window_info.physical_size().height,
+                    window_info.scale() as f32,
+                );
+            }
+            baseview::Event::Window(baseview::WindowEvent::Focused) => {
+                self.on_focus_changed(true);
+            }
+            baseview::Event::Window(baseview::WindowEvent::Unfocused) => {
+                self.on_focus_changed(false);
+            }
+            baseview::Event::Window(_) => return false,
+        }
+
+        true
+    }
+
+    /// Track whether the window has the keyboard focus. When the focus is lost, all pressed
+    /// keys and pointer buttons are released, because their release events would go to
+    /// another window.
+    pub fn on_focus_changed(&mut self, has_focus: bool) {
+        self.egui_input.has_focus = has_focus;
+
+        if !has_focus {
+            self.release_all();
+        }
+    }
+
+    /// Release all pressed pointer buttons, e.g. when the window lost the pointer capture
+    /// and will not receive their release events.
+    pub fn on_pointer_capture_lost(&mut self) {
+        for (button, last_pos) in std::mem::take(&mut self.buttons_down) {
+            self.push_button_release(button, last_pos);
+        }
+
+        if let Some((_, pos)) = self.pointer_touch.take() {
+            self.push_touch_pointer_release(pos);
+        }
+    }
+
+    /// Release all pressed keys, modifiers and pointer buttons.
+    fn release_all(&mut self) {
+        let modifiers = egui::Modifiers::default();
+
+        for key in self.keys_down.drain(..) {
+            self.egui_input.events.push(egui::Event::Key {
+                key,
+                pressed: false,
+                modifiers,
+            });
+        }
+
+        self.egui_input.modifiers = modifiers;
+        self.on_pointer_capture_lost();
+    }
+
+    fn push_button_release(&mut self, button: egui::PointerButton, last_pos: Pos2) {
+        self.egui_input.events.push(egui::Event::PointerButton {
+            pos: self.mouse_pos.unwrap_or(last_pos),
+            button,
+            pressed: false,
+            modifiers: self.egui_input.modifiers,
+        });
+    }
+
+    fn push_touch_pointer_release(&mut self, pos: Pos2) {
+        self.egui_input.events.push(egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed: false,
+            modifiers: self.egui_input.modifiers,
+        });
+        self.egui_input.events.push(egui::Event::PointerGone);
+    }
+
+    /// Translate a touch or pen event.
+    ///
+    /// Every touch is forwarded as an [`egui::Event::Touch`], from which egui recognizes
+    /// multi-touch gestures, e.g. pinching to zoom. The first touch additionally acts as the
+    /// primary pointer button, so widgets that only know about the pointer work with touch too.
+    pub fn on_touch_event(&mut self, event: &TouchEvent) {
+        let pos = self.to_points(&event.position);
+
+        self.egui_input.events.push(egui::Event::Touch {
+            device_id: egui::TouchDeviceId(event.device_id),
+            id: egui::TouchId(event.id),
+            phase: event.phase,
+            pos,
+            force: event.pressure.unwrap_or(0.0),
+        });
+
+        match event.phase {
+            egui::TouchPhase::Start => {
+                if self.pointer_touch.is_none() {
+                    self.pointer_touch = Some((event.id, pos));
+                    self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                    self.egui_input.events.push(egui::Event::PointerButton {
+                        pos,
+                        button: egui::PointerButton::Primary,
+                        pressed: true,
+                        modifiers: self.egui_input.modifiers,
+                    });
+                }
+            }
+            egui::TouchPhase::Move => {
+                if let Some((id, last_pos)) = &mut self.pointer_touch {
+                    if *id == event.id {
+                        *last_pos = pos;
+                        self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                    }
+                }
+            }
+            egui::TouchPhase::End | egui::TouchPhase::Cancel => {
+                if matches!(self.pointer_touch, Some((id, _)) if id == event.id) {
+                    self.pointer_touch = None;
+                    self.push_touch_pointer_release(pos);
+                }
+            }
+        }
+    }
+
+    /// Translate all input that was sent through `sender` since the last call.
+    pub fn receive(&mut self, sender: &InputSender) {
+        for input in sender.take() {
+            self.on_external_input(&input);
+        }
+    }
+
+    /// Translate input that baseview does not report.
+    pub fn on_external_input(&mut self, input: &ExternalInput) {
+        match input {
+            ExternalInput::Touch(event) => self.on_touch_event(event),
+            ExternalInput::MomentumScroll(delta) => self.on_momentum_scroll(delta),
+            ExternalInput::Magnify(magnification) => self.on_magnify(*magnification),
+            ExternalInput::PointerCaptureLost => self.on_pointer_capture_lost(),
+            #[cfg(feature = "accesskit")]
+            ExternalInput::AccessKitAction(request) => {
+                self.on_accesskit_action_request(request.clone())
+            }
+        }
+    }
+
+    /// Translate an action requested by an assistive technology, e.g. a screen reader
+    /// clicking a button, see [`crate::accessibility`].
+    #[cfg(feature = "accesskit")]
+    pub fn on_accesskit_action_request(&mut self, request: egui::accesskit::ActionRequest) {
+        self.egui_input
+            .events
+            .push(egui::Event::AccessKitActionRequest(request));
+    }
+
+    /// Update the pressed key modifiers when a mouse event has sent a new set of modifiers.
+    fn update_modifiers(&mut self, modifiers: &Modifiers) {
+        self.egui_input.modifiers.alt = !(*modifiers & Modifiers::ALT).is_empty();
+        self.egui_input.modifiers.shift = !(*modifiers & Modifiers::SHIFT).is_empty();
+        self.egui_input.modifiers.command = !(*modifiers & Modifiers::CONTROL).is_empty();
+    }
+
+    pub fn on_mouse_event(&mut self, event: &baseview::MouseEvent) {
+        match event {
+            baseview::MouseEvent::CursorMoved {
+                position,
+                modifiers,
+            } => {
+                self.update_modifiers(modifiers);
+
+                let pos = self.to_points(position);
+                self.mouse_pos = Some(pos);
+                self.egui_input.events.push(egui::Event::PointerMoved(pos));
+
+                for (_, last_pos) in &mut self.buttons_down {
+                    *last_pos = pos;
+                }
+            }
+            baseview::MouseEvent::ButtonPressed { button, modifiers } => {
+                self.update_modifiers(modifiers);
+
+                if let Some(pos) = self.mouse_pos {
+                    if let Some(button) = translate_mouse_button(*button) {
+                        if let Some(index) =
+                            self.buttons_down.iter().position(|(down, _)| *down == button)
+                        {
+                            // We missed the release, e.g. because it happened outside of
+                            // the window, so release the button before pressing it again.
+                            let (button, last_pos) = self.buttons_down.remove(index);
+                            self.push_button_release(button, last_pos);
+                        }
+                        self.buttons_down.push((button, pos));
+                        self.egui_input.events.push(egui::Event::PointerButton {
+                            pos,
+                            button,
+                            pressed: true,
+                            modifiers: self.egui_input.modifiers,
+                        });
+                    }
+                }
+            }
+            baseview::MouseEvent::ButtonReleased { button, modifiers } => {
+                self.update_modifiers(modifiers);
+
+                if let Some(button) = translate_mouse_button(*button) {
+                    if let Some(index) =
+                        self.buttons_down.iter().position(|(down, _)| *down == button)
+                    {
+                        // This also releases buttons that are released outside of the
+                        // window, where we don't know the pointer position anymore.
+                        let (button, last_pos) = self.buttons_down.remove(index);
+                        self.push_button_release(button, last_pos);
+                    } else if let Some(pos) = self.mouse_pos {
+                        self.egui_input.events.push(egui::Event::PointerButton {
+                            pos,
+                            button,
+                            pressed: false,
+                            modifiers: self.egui_input.modifiers,
+                        });
+                    }
+                }
+            }
+            baseview::MouseEvent::WheelScrolled {
+                delta: scroll_delta,
+                modifiers,
+            } => {
+                self.update_modifiers(modifiers);
+
+                let kind = match scroll_delta {
+                    baseview::ScrollDelta::Lines { .. } => ScrollKind::Wheel,
+                    baseview::ScrollDelta::Pixels { .. } => ScrollKind::Smooth,
+                };
+                self.push_scroll(scroll_delta, kind);
+            }
+            baseview::MouseEvent::CursorLeft => {
+                self.mouse_pos = None;
+                self.egui_input.events.push(egui::Event::PointerGone);
+            }
+            baseview::MouseEvent::DragEntered {
+                position,
+                modifiers,
+                data,
+            }
+            | baseview::MouseEvent::DragMoved {
+                position,
+                modifiers,
+                data,
+            } => {
+                self.update_modifiers(modifiers);
+
+                let pos = self.to_points(position);
+                self.mouse_pos = Some(pos);
+                self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                self.egui_input.hovered_files = dragged_paths(data)
+                    .map(|path| egui::HoveredFile {
+                        path: Some(path.clone()),
+                        ..Default::default()
+                    })
+                    .collect();
+            }
+            baseview::MouseEvent::DragLeft => {
+                self.egui_input.hovered_files.clear();
+                self.mouse_pos = None;
+                self.egui_input.events.push(egui::Event::PointerGone);
+            }
+            baseview::MouseEvent::DragDropped {
+                position,
+                modifiers,
+                data,
+            } => {
+                self.update_modifiers(modifiers);
+
+                let pos = self.to_points(position);
+                self.mouse_pos = Some(pos);
+                self.egui_input.events.push(egui::Event::PointerMoved(pos));
+                self.egui_input.hovered_files.clear();
+                if self.drop_accepted {
+                    self.egui_input
+                        .dropped_files
+                        .extend(dragged_paths(data).map(|path| egui::DroppedFile {
+                            path: Some(path.clone()),
+                            name: path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_default(),
+                            ..Default::default()
+                        }));
+                }
+            }
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+    }
+
+    /// Translate the momentum scrolling that continues after the fingers were lifted from a
+    /// trackpad. baseview does not tell these apart from regular scroll events yet, so
+    /// whoever receives them from the OS has to pass them to the window as
+    /// [`ExternalInput::MomentumScroll`], or here.
+    pub fn on_momentum_scroll(&mut self, delta: &baseview::ScrollDelta) {
+        self.push_scroll(delta, ScrollKind::Momentum);
+    }
+
+    /// Translate a trackpad pinch gesture, which baseview does not report yet, see
+    /// [`ExternalInput::Magnify`]. `magnification` is the amount the content
+    /// should grow by, e.g. `0.1` for 10% larger, like macOS' `NSEvent.magnification`.
+    pub fn on_magnify(&mut self, magnification: f32) {
+        let factor = 1.0 + magnification;
+        if factor.is_finite() && factor > 0.0 {
+            self.egui_input.events.push(egui::Event::Zoom(factor));
+        }
+    }
+
+    fn push_scroll(&mut self, scroll_delta: &baseview::ScrollDelta, kind: ScrollKind) {
+        self.scroll_kind = Some(kind);
+
+        let delta = match scroll_delta {
+            baseview::ScrollDelta::Lines { x, y } => {
+                let points_per_scroll_line = 50.0; // Scroll speed decided by consensus: https://github.com/emilk/egui/issues/461
+                egui::vec2(*x, *y) * points_per_scroll_line
+            }
+            baseview::ScrollDelta::Pixels { x, y } => egui::vec2(*x, *y) / self.pixels_per_point,
+        };
+
+        if self.egui_input.modifiers.ctrl || self.egui_input.modifiers.command {
+            // Treat as zoom instead:
+            let factor = (delta.y / 200.0).exp();
+            self.egui_input.events.push(egui::Event::Zoom(factor));
+        } else if self.egui_input.modifiers.shift {
+            // Treat as horizontal scrolling.
+            // Note: one Mac we already get horizontal scroll events when shift is down.
+            self.egui_input
+                .events
+                .push(egui::Event::Scroll(egui::vec2(delta.x + delta.y, 0.0)));
+        } else {
+            self.egui_input.events.push(egui::Event::Scroll(delta));
+        }
+    }
+
+    pub fn on_keyboard_event(
+        &mut self,
+        event: &keyboard_types::KeyboardEvent,
+        clipboard: Option<&mut dyn ClipboardSource>,
+    ) {
+        let input = EguiKeyboardInput::from_keyboard_event(event, clipboard);
+
+        for event in &input.events {
+            if let egui::Event::Key { key, pressed, .. } = event {
+                if !*pressed {
+                    self.keys_down.retain(|down| down != key);
+                } else if !self.keys_down.contains(key) {
+                    self.keys_down.push(*key);
+                }
+            }
+        }
+
+        input.apply_on_raw_input(&mut self.egui_input);
+    }
+}
+
+/// The area egui should use, in points.
+pub(crate) fn logical_screen_rect(
+    physical_width: u32,
+    physical_height: u32,
+    pixels_per_point: f32,
+) -> egui::Rect {
+    egui::Rect::from_min_size(
+        Pos2::new(0f32, 0f32),
+        egui::vec2(
+            physical_width as f32 / pixels_per_point,
+            physical_height as f32 / pixels_per_point,
+        ),
+    )
+}
+
+/// The paths of the files being dragged.
+fn dragged_paths(data: &baseview::DropData) -> impl Iterator<Item = &std::path::PathBuf> {
+    let paths: &[std::path::PathBuf] = match data {
+        baseview::DropData::Files(paths) => paths,
+        baseview::DropData::None => &[],
+    };
+    paths.iter()
+}
+
+pub struct EguiKeyboardInput {
+    events: Vec<egui::Event>,
+    modifiers: egui::Modifiers,
+}
+impl EguiKeyboardInput {
+    pub fn from_keyboard_event(
+        event: &keyboard_types::KeyboardEvent,
+        clipboard: Option<&mut dyn ClipboardSource>,
+    ) -> EguiKeyboardInput {
+        let mut events = vec![];
+        let mut modifiers = translate_modifiers(&event.modifiers);
+
+        use keyboard_types::Code;
+
+        let pressed = event.state == keyboard_types::KeyState::Down;
+
+        match event.code {
+            Code::ShiftLeft | Code::ShiftRight => modifiers.shift = pressed,
+            Code::ControlLeft | Code::ControlRight => {
+                modifiers.ctrl = pressed;
+
+                #[cfg(not(target_os = "macos"))]
+                {
+                    modifiers.command = pressed;
+                }
+            }
+            Code::AltLeft | Code::AltRight => modifiers.alt = pressed,
+            Code::MetaLeft | Code::MetaRight => {
+                #[cfg(target_os = "macos")]
+                {
+                    modifiers.mac_cmd = pressed;
+                    modifiers.command = pressed;
+                }
+                () // prevent `rustfmt` from breaking this
+            }
+            _ => (),
+        }
+
+        if let Some(key) = translate_virtual_key_code(event.code) {
+            events.push(egui::Event::Key { key, pressed, modifiers });
+        }
+
+        if pressed {
+            // VirtualKeyCode::Paste etc in winit are broken/untrustworthy,
+            // so we detect these things manually:
+            if is_cut_command(modifiers, event.code) {
+                events.push(egui::Event::Cut);
+            } else if is_copy_command(modifiers, event.code) {
+                events.push(egui::Event::Copy);
+            } else if is_paste_command(modifiers, event.code) {
+                if let Some(clipboard) = clipboard {
+                    if let Some(contents) = clipboard.text() {
+                        events.push(egui::Event::Paste(contents));
+                    }
+                    if let Some(data) = clipboard.mime_data("application/dspstudio") {
+                        events.push(
+                            egui::Event::PasteMime(ClipboardData {
+                                data,
+                                mime: ClipboardMime::Specific("application/dspstudio".to_string())
+                            })
+                        );
+                    }
+                }
+            } else if let keyboard_types::Key::Character(written) = &event.key {
+                if !modifiers.ctrl && !modifiers.command {
+                    events.push(egui::Event::Text(written.clone()));
+                }
+            }
+        }
+        EguiKeyboardInput {
+            events,
+            modifiers
+        }
+    }
+
+    pub fn apply_on_input(self, input_mut: &mut egui::InputState) {
+        for event in self.events {
+            if let egui::Event::Key { key, pressed, .. } = &event {
+                if *pressed {
+                    input_mut.keys_down.insert(*key);
+                } else {
+                    input_mut.keys_down.remove(key);
+                }
+            }
+            input_mut.raw.events.push(event.clone());
+            input_mut.events.push(event);
+        }
+        input_mut.raw.modifiers = self.modifiers;
+        input_mut.modifiers = self.modifiers;
+    }
+
+    pub fn apply_on_raw_input(self, raw_input_mut: &mut egui::RawInput) {
+        for event in self.events {
+            raw_input_mut.events.push(event.clone());
+        }
+        raw_input_mut.modifiers = self.modifiers;
+    }
+}
+
+pub fn translate_mouse_button(button: baseview::MouseButton) -> Option<egui::PointerButton> {
+    match button {
+        baseview::MouseButton::Left => Some(egui::PointerButton::Primary),
+        baseview::MouseButton::Right => Some(egui::PointerButton::Secondary),
+        baseview::MouseButton::Middle => Some(egui::PointerButton::Middle),
+        _ => None,
+    }
+}
+
+pub fn translate_virtual_key_code(key: keyboard_types::Code) -> Option<egui::Key> {
+    use egui::Key;
+    use keyboard_types::Code;
+
+    Some(match key {
+        Code::ArrowDown => Key::ArrowDown,
+        Code::ArrowLeft => Key::ArrowLeft,
+        Code::ArrowRight => Key::ArrowRight,
+        Code::ArrowUp => Key::ArrowUp,
+
+        Code::Escape => Key::Escape,
+        Code::Tab => Key::Tab,
+        Code::Backspace => Key::Backspace,
+        Code::Enter | Code::NumpadEnter => Key::Enter,
+        Code::Space => Key::Space,
+
+        Code::Insert => Key::Insert,
+        Code::Delete => Key::Delete,
+        Code::Home => Key::Home,
+        Code::End => Key::End,
+        Code::PageUp => Key::PageUp,
+        Code::PageDown => Key::PageDown,
+
+        Code::Digit0 | Code::Numpad0 => Key::Num0,
+        Code::Digit1 | Code::Numpad1 => Key::Num1,
+        Code::Digit2 | Code::Numpad2 => Key::Num2,
+        Code::Digit3 | Code::Numpad3 => Key::Num3,
+        Code::Digit4 | Code::Numpad4 => Key::Num4,
+        Code::Digit5 | Code::Numpad5 => Key::Num5,
+        Code::Digit6 | Code::Numpad6 => Key::Num6,
+        Code::Digit7 | Code::Numpad7 => Key::Num7,
+        Code::Digit8 | Code::Numpad8 => Key::Num8,
+        Code::Digit9 | Code::Numpad9 => Key::Num9,
+
+        Code::KeyA => Key::A,
+        Code::KeyB => Key::B,
+        Code::KeyC => Key::C,
+        Code::KeyD => Key::D,
+        Code::KeyE => Key::E,
+        Code::KeyF => Key::F,
+        Code::KeyG => Key::G,
+        Code::KeyH => Key::H,
+        Code::KeyI => Key::I,
+        Code::KeyJ => Key::J,
+        Code::KeyK => Key::K,
+        Code::KeyL => Key::L,
+        Code::KeyM => Key::M,
+        Code::KeyN => Key::N,
+        Code::KeyO => Key::O,
+        Code::KeyP => Key::P,
+        Code::KeyQ => Key::Q,
+        Code::KeyR => Key::R,
+        Code::KeyS => Key::S,
+        Code::KeyT => Key::T,
+        Code::KeyU => Key::U,
+        Code::KeyV => Key::V,
+        Code::KeyW => Key::W,
+        Code::KeyX => Key::X,
+        Code::KeyY => Key::Y,
+        Code::KeyZ => Key::Z,
+        _ => {
+            return None;
+        }
+    })
+}
+
+pub fn is_cut_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
+    (modifiers.command && keycode == keyboard_types::Code::KeyX)
+        || (cfg!(target_os = "windows")
+            && modifiers.shift
+            && keycode == keyboard_types::Code::Delete)
+}
+
+pub fn is_copy_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
+    (modifiers.command && keycode == keyboard_types::Code::KeyC)
+        || (cfg!(target_os = "windows")
+            && modifiers.ctrl
+            && keycode == keyboard_types::Code::Insert)
+}
+
+pub fn is_paste_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
+    (modifiers.command && keycode == keyboard_types::Code::KeyV)
+        || (cfg!(target_os = "windows")
+            && modifiers.shift
+            && keycode == keyboard_types::Code::Insert)
+}
+
+pub fn translate_modifiers(modifiers: &keyboard_types::Modifiers) -> egui::Modifiers {
+    egui::Modifiers {
+        alt: modifiers.contains(keyboard_types::Modifiers::ALT),
+        command: modifiers.contains(keyboard_types::Modifiers::META) || (!cfg!(target_os = "macos") && modifiers.contains(keyboard_types::Modifiers::CONTROL)),
+        ctrl: modifiers.contains(keyboard_types::Modifiers::CONTROL) || (!cfg!(target_os = "macos") && modifiers.contains(keyboard_types::Modifiers::META)),
+        mac_cmd: cfg!(target_os = "macos") && modifiers.contains(keyboard_types::Modifiers::META),
+        shift: modifiers.contains(keyboard_types::Modifiers::SHIFT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::vec2;
+
+    fn translator(pixels_per_point: f32) -> InputTranslator {
+        let mut translator = InputTranslator::default();
+        translator.set_pixels_per_point(pixels_per_point);
+        translator
+    }
+
+    fn command_modifier() -> Modifiers {
+        if cfg!(target_os = "macos") {
+            Modifiers::META
+        } else {
+            Modifiers::CONTROL
+        }
+    }
+
+    fn move_to(translator: &mut InputTranslator, x: f64, y: f64) {
+        translator.on_mouse_event(&baseview::MouseEvent::CursorMoved {
+            position: baseview::Point::new(x, y),
+            modifiers: Modifiers::empty(),
+        });
+    }
+
+    fn scroll(
+        translator: &mut InputTranslator,
+        delta: baseview::ScrollDelta,
+        modifiers: Modifiers,
+    ) {
+        translator.on_mouse_event(&baseview::MouseEvent::WheelScrolled { delta, modifiers });
+    }
+
+    fn key_down(
+        code: keyboard_types::Code,
+        key: keyboard_types::Key,
+        modifiers: Modifiers,
+    ) -> keyboard_types::KeyboardEvent {
+        keyboard_types::KeyboardEvent {
+            state: keyboard_types::KeyState::Down,
+            key,
+            code,
+            modifiers,
+            ..Default::default()
+        }
+    }
+
+    fn take_events(translator: &mut InputTranslator) -> Vec<egui::Event> {
+        std::mem::take(&mut translator.egui_input.events)
+    }
+
+    #[test]
+    fn button_press_without_prior_move_is_ignored() {
+        let mut translator = translator(1.0);
+
+        translator.on_mouse_event(&baseview::MouseEvent::ButtonPressed {
+            button: baseview::MouseButton::Left,
+            modifiers: Modifiers::empty(),
+        });
+
+        assert!(take_events(&mut translator).is_empty());
+    }
+
+    #[test]
+    fn button_press_uses_last_cursor_position() {
+        let mut translator = translator(1.0);
+
+        move_to(&mut translator, 10.0, 20.0);
+        translator.on_mouse_event(&baseview::MouseEvent::ButtonPressed {
+            button: baseview::MouseButton::Right,
+            modifiers: Modifiers::empty(),
+        });
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![
+                egui::Event::PointerMoved(pos2(10.0, 20.0)),
+                egui::Event::PointerButton {
+                    pos: pos2(10.0, 20.0),
+                    button: egui::PointerButton::Secondary,
+                    pressed: true,
+                    modifiers: egui::Modifiers::default(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn line_scroll_is_independent_of_scale() {
+        for pixels_per_point in [1.0, 2.0] {
+            let mut translator = translator(pixels_per_point);
+
+            let delta = baseview::ScrollDelta::Lines { x: 0.0, y: 1.0 };
+            scroll(&mut translator, delta, Modifiers::empty());
+
+            assert_eq!(
+                take_events(&mut translator),
+                vec![egui::Event::Scroll(vec2(0.0, 50.0))]
+            );
+        }
+    }
+
+    #[test]
+    fn pixel_scroll_is_converted_to_points() {
+        let mut translator = translator(2.0);
+
+        let delta = baseview::ScrollDelta::Pixels { x: 10.0, y: 100.0 };
+        scroll(&mut translator, delta, Modifiers::empty());
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![egui::Event::Scroll(vec2(5.0, 50.0))]
+        );
+    }
+
+    #[test]
+    fn pixel_scroll_keeps_scale_across_frames() {
+        let mut translator = translator(2.0);
+
+        // A frame takes `pixels_per_point` out of the raw input.
+        translator.egui_input.take();
+        let delta = baseview::ScrollDelta::Pixels { x: 0.0, y: 100.0 };
+        scroll(&mut translator, delta, Modifiers::empty());
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![egui::Event::Scroll(vec2(0.0, 50.0))]
+        );
+    }
+
+    #[test]
+    fn shift_scroll_is_horizontal() {
+        let mut translator = translator(1.0);
+
+        let delta = baseview::ScrollDelta::Lines { x: 0.0, y: 1.0 };
+        scroll(&mut translator, delta, Modifiers::SHIFT);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![egui::Event::Scroll(vec2(50.0, 0.0))]
+        );
+    }
+
+    #[test]
+    fn cursor_left_is_pointer_gone() {
+        let mut translator = translator(1.0);
+
+        move_to(&mut translator, 1.0, 1.0);
+        take_events(&mut translator);
+        translator.on_mouse_event(&baseview::MouseEvent::CursorLeft);
+
+        assert_eq!(take_events(&mut translator), vec![egui::Event::PointerGone]);
+
+        // Without a known position, presses are dropped again.
+        translator.on_mouse_event(&baseview::MouseEvent::ButtonPressed {
+            button: baseview::MouseButton::Left,
+            modifiers: Modifiers::empty(),
+        });
+        assert!(take_events(&mut translator).is_empty());
+    }
+
+    #[test]
+    fn cut_copy_and_paste_are_detected() {
+        use keyboard_types::{Code, Key};
+
+        let cases = [
+            (Code::KeyX, egui::Event::Cut),
+            (Code::KeyC, egui::Event::Copy),
+        ];
+        for (code, expected) in cases {
+            let mut translator = translator(1.0);
+            let letter = Key::Character(String::from("x"));
+
+            translator.on_keyboard_event(&key_down(code, letter, command_modifier()), None);
+
+            let events = take_events(&mut translator);
+            assert!(events.contains(&expected), "{:?} in {:?}", expected, events);
+            assert!(!events.iter().any(|e| matches!(e, egui::Event::Text(_))));
+        }
+
+        // Without a clipboard there is nothing to paste, but no text is inserted either.
+        let mut translator = translator(1.0);
+        let event = key_down(Code::KeyV, Key::Character(String::from("v")), command_modifier());
+        translator.on_keyboard_event(&event, None);
+
+        let events = take_events(&mut translator);
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, egui::Event::Paste(_) | egui::Event::Text(_))));
+    }
+
+    #[test]
+    fn plain_character_is_text() {
+        use keyboard_types::{Code, Key};
+
+        let mut translator = translator(1.0);
+        let event = key_down(Code::KeyA, Key::Character(String::from("a")), Modifiers::empty());
+        translator.on_keyboard_event(&event, None);
+
+        let events = take_events(&mut translator);
+        assert!(events.contains(&egui::Event::Text(String::from("a"))));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            egui::Event::Key { key: egui::Key::A, pressed: true, .. }
+        )));
+    }
+
+    #[test]
+    fn resize_sets_screen_rect_in_points() {
+        let mut translator = InputTranslator::default();
+        let window_info = baseview::WindowInfo::from_physical_size(
+            baseview::PhySize::new(800, 600),
+            2.0,
+        );
+
+        let used = translator.on_event(
+            &baseview::Event::Window(baseview::WindowEvent::Resized(window_info)),
+            None,
+        );
+
+        assert!(used);
+        let raw_input = translator.take_raw_input();
+        assert_eq!(raw_input.pixels_per_point, Some(2.0));
+        assert_eq!(raw_input.screen_rect.map(|rect| rect.size()), Some(vec2(400.0, 300.0)));
+    }
+
+    fn drag_event(files: &[&str]) -> baseview::MouseEvent {
+        baseview::MouseEvent::DragEntered {
+            position: baseview::Point::new(5.0, 6.0),
+            modifiers: Modifiers::empty(),
+            data: baseview::DropData::Files(files.iter().map(|file| file.into()).collect()),
+        }
+    }
+
+    #[test]
+    fn dragged_files_are_hovered() {
+        let mut translator = translator(1.0);
+
+        translator.on_mouse_event(&drag_event(&["/samples/kick.wav"]));
+
+        let raw_input = translator.take_raw_input();
+        assert_eq!(raw_input.events, vec![egui::Event::PointerMoved(pos2(5.0, 6.0))]);
+        assert_eq!(raw_input.hovered_files.len(), 1);
+        assert_eq!(
+            raw_input.hovered_files[0].path,
+            Some(std::path::PathBuf::from("/samples/kick.wav"))
+        );
+
+        translator.on_mouse_event(&baseview::MouseEvent::DragLeft);
+
+        assert!(translator.raw_input().hovered_files.is_empty());
+    }
+
+    #[test]
+    fn dropped_files_are_moved_to_dropped() {
+        let mut translator = translator(1.0);
+
+        translator.on_mouse_event(&drag_event(&["/samples/kick.wav"]));
+        translator.on_mouse_event(&baseview::MouseEvent::DragDropped {
+            position: baseview::Point::new(5.0, 6.0),
+            modifiers: Modifiers::empty(),
+            data: baseview::DropData::Files(vec!["/samples/kick.wav".into()]),
+        });
+
+        let raw_input = translator.take_raw_input();
+        assert!(raw_input.hovered_files.is_empty());
+        assert_eq!(raw_input.dropped_files.len(), 1);
+        assert_eq!(raw_input.dropped_files[0].name, "kick.wav");
+
+        // Dropped files are only delivered once.
+        assert!(translator.take_raw_input().dropped_files.is_empty());
+    }
+
+    #[test]
+    fn rejected_drop_delivers_no_files() {
+        let mut translator = translator(1.0);
+        translator.set_drop_accepted(false);
+
+        translator.on_mouse_event(&drag_event(&["/samples/kick.wav"]));
+        translator.on_mouse_event(&baseview::MouseEvent::DragDropped {
+            position: baseview::Point::new(5.0, 6.0),
+            modifiers: Modifiers::empty(),
+            data: baseview::DropData::Files(vec!["/samples/kick.wav".into()]),
+        });
+
+        let raw_input = translator.take_raw_input();
+        assert!(raw_input.hovered_files.is_empty());
+        assert!(raw_input.dropped_files.is_empty());
+    }
+
+    #[test]
+    fn focus_loss_releases_keys_and_buttons() {
+        use keyboard_types::{Code, Key};
+
+        let mut translator = translator(1.0);
+        move_to(&mut translator, 3.0, 4.0);
+        translator.on_mouse_event(&baseview::MouseEvent::ButtonPressed {
+            button: baseview::MouseButton::Left,
+            modifiers: Modifiers::empty(),
+        });
+        let event = key_down(Code::KeyA, Key::Character(String::from("a")), Modifiers::SHIFT);
+        translator.on_keyboard_event(&event, None);
+        take_events(&mut translator);
+
+        translator.on_event(&baseview::Event::Window(baseview::WindowEvent::Unfocused), None);
+
+        let raw_input = translator.take_raw_input();
+        assert!(!raw_input.has_focus);
+        assert_eq!(raw_input.modifiers, egui::Modifiers::default());
+        assert_eq!(
+            raw_input.events,
+            vec![
+                egui::Event::Key {
+                    key: egui::Key::A,
+                    pressed: false,
+                    modifiers: egui::Modifiers::default(),
+                },
+                egui::Event::PointerButton {
+                    pos: pos2(3.0, 4.0),
+                    button: egui::PointerButton::Primary,
+                    pressed: false,
+                    modifiers: egui::Modifiers::default(),
+                },
+            ]
+        );
+
+        // Nothing is pressed anymore, so regaining the focus releases nothing.
+        translator.on_event(&baseview::Event::Window(baseview::WindowEvent::Focused), None);
+
+        let raw_input = translator.take_raw_input();
+        assert!(raw_input.has_focus);
+        assert!(raw_input.events.is_empty());
+    }
+
+    fn press(translator: &mut InputTranslator, button: baseview::MouseButton) {
+        translator.on_mouse_event(&baseview::MouseEvent::ButtonPressed {
+            button,
+            modifiers: Modifiers::empty(),
+        });
+    }
+
+    fn release(translator: &mut InputTranslator, button: baseview::MouseButton) {
+        translator.on_mouse_event(&baseview::MouseEvent::ButtonReleased {
+            button,
+            modifiers: Modifiers::empty(),
+        });
+    }
+
+    fn primary_button(pos: Pos2, pressed: bool) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: egui::Modifiers::default(),
+        }
+    }
+
+    #[test]
+    fn release_outside_of_window_is_not_dropped() {
+        let mut translator = translator(1.0);
+
+        move_to(&mut translator, 10.0, 10.0);
+        press(&mut translator, baseview::MouseButton::Left);
+        move_to(&mut translator, 0.0, 12.0);
+        translator.on_mouse_event(&baseview::MouseEvent::CursorLeft);
+        take_events(&mut translator);
+
+        release(&mut translator, baseview::MouseButton::Left);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![primary_button(pos2(0.0, 12.0), false)]
+        );
+
+        // A second release event is not turned into another release.
+        release(&mut translator, baseview::MouseButton::Left);
+        assert!(take_events(&mut translator).is_empty());
+    }
+
+    #[test]
+    fn missed_release_is_synthesized_before_next_press() {
+        let mut translator = translator(1.0);
+
+        move_to(&mut translator, 10.0, 10.0);
+        press(&mut translator, baseview::MouseButton::Left);
+        move_to(&mut translator, 20.0, 20.0);
+        take_events(&mut translator);
+
+        press(&mut translator, baseview::MouseButton::Left);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![
+                primary_button(pos2(20.0, 20.0), false),
+                primary_button(pos2(20.0, 20.0), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_loss_releases_buttons() {
+        let mut translator = translator(1.0);
+
+        move_to(&mut translator, 10.0, 10.0);
+        press(&mut translator, baseview::MouseButton::Left);
+        take_events(&mut translator);
+
+        let sender = InputSender::default();
+        sender.send(ExternalInput::PointerCaptureLost);
+        translator.receive(&sender);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![primary_button(pos2(10.0, 10.0), false)]
+        );
+
+        translator.on_pointer_capture_lost();
+        assert!(take_events(&mut translator).is_empty());
+    }
+
+    fn touch(id: u64, phase: egui::TouchPhase, x: f64, y: f64) -> TouchEvent {
+        TouchEvent {
+            device_id: 0,
+            id,
+            phase,
+            position: baseview::Point::new(x, y),
+            pressure: None,
+        }
+    }
+
+    fn touch_event(id: u64, phase: egui::TouchPhase, pos: Pos2, force: f32) -> egui::Event {
+        egui::Event::Touch {
+            device_id: egui::TouchDeviceId(0),
+            id: egui::TouchId(id),
+            phase,
+            pos,
+            force,
+        }
+    }
+
+    #[test]
+    fn first_touch_acts_as_pointer() {
+        use egui::TouchPhase;
+
+        let mut translator = translator(1.0);
+
+        translator.on_touch_event(&touch(1, TouchPhase::Start, 10.0, 10.0));
+        translator.on_touch_event(&touch(1, TouchPhase::Move, 20.0, 10.0));
+        translator.on_touch_event(&touch(1, TouchPhase::End, 20.0, 10.0));
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![
+                touch_event(1, TouchPhase::Start, pos2(10.0, 10.0), 0.0),
+                egui::Event::PointerMoved(pos2(10.0, 10.0)),
+                primary_button(pos2(10.0, 10.0), true),
+                touch_event(1, TouchPhase::Move, pos2(20.0, 10.0), 0.0),
+                egui::Event::PointerMoved(pos2(20.0, 10.0)),
+                touch_event(1, TouchPhase::End, pos2(20.0, 10.0), 0.0),
+                primary_button(pos2(20.0, 10.0), false),
+                egui::Event::PointerGone,
+            ]
+        );
+    }
+
+    #[test]
+    fn second_touch_is_only_a_touch() {
+        use egui::TouchPhase;
+
+        let mut translator = translator(1.0);
+
+        translator.on_touch_event(&touch(1, TouchPhase::Start, 10.0, 10.0));
+        take_events(&mut translator);
+
+        translator.on_touch_event(&touch(2, TouchPhase::Start, 30.0, 10.0));
+        translator.on_touch_event(&touch(2, TouchPhase::Move, 40.0, 10.0));
+        translator.on_touch_event(&touch(2, TouchPhase::Cancel, 40.0, 10.0));
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![
+                touch_event(2, TouchPhase::Start, pos2(30.0, 10.0), 0.0),
+                touch_event(2, TouchPhase::Move, pos2(40.0, 10.0), 0.0),
+                touch_event(2, TouchPhase::Cancel, pos2(40.0, 10.0), 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn pen_pressure_is_forwarded() {
+        let mut translator = translator(1.0);
+
+        translator.on_touch_event(&TouchEvent {
+            pressure: Some(0.5),
+            ..touch(1, egui::TouchPhase::Start, 10.0, 10.0)
+        });
+
+        assert_eq!(
+            take_events(&mut translator)[0],
+            touch_event(1, egui::TouchPhase::Start, pos2(10.0, 10.0), 0.5)
+        );
+    }
+
+    #[test]
+    fn sent_touches_are_received() {
+        use egui::TouchPhase;
+
+        let sender = InputSender::default();
+        let touch_handler = sender.clone();
+        std::thread::spawn(move || {
+            touch_handler.send(ExternalInput::Touch(touch(1, TouchPhase::Start, 10.0, 20.0)));
+        })
+        .join()
+        .unwrap();
+
+        let mut translator = translator(1.0);
+        translator.receive(&sender);
+
+        assert_eq!(
+            take_events(&mut translator)[0],
+            touch_event(1, TouchPhase::Start, pos2(10.0, 20.0), 0.0)
+        );
+
+        // Every input is only received once.
+        translator.receive(&sender);
+        assert!(take_events(&mut translator).is_empty());
+    }
+
+    #[test]
+    fn focus_loss_releases_touch_pointer() {
+        let mut translator = translator(1.0);
+
+        translator.on_touch_event(&touch(1, egui::TouchPhase::Start, 10.0, 10.0));
+        take_events(&mut translator);
+
+        translator.on_focus_changed(false);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![
+                primary_button(pos2(10.0, 10.0), false),
+                egui::Event::PointerGone
+            ]
+        );
+    }
+
+    #[test]
+    fn ui_scale_converts_positions_to_points() {
+        let mut translator = translator(2.0 * 1.5);
+        translator.set_ui_scale(2.0);
+
+        move_to(&mut translator, 100.0, 60.0);
+        press(&mut translator, baseview::MouseButton::Left);
+        translator.on_touch_event(&touch(1, egui::TouchPhase::Start, 40.0, 20.0));
+
+        let events = take_events(&mut translator);
+        assert_eq!(
+            events[..2],
+            [
+                egui::Event::PointerMoved(pos2(50.0, 30.0)),
+                primary_button(pos2(50.0, 30.0), true),
+            ]
+        );
+        assert_eq!(
+            events[2],
+            touch_event(1, egui::TouchPhase::Start, pos2(20.0, 10.0), 0.0)
+        );
+    }
+
+    #[test]
+    fn scroll_kind_is_kept_for_one_frame() {
+        let mut translator = translator(1.0);
+
+        scroll(
+            &mut translator,
+            baseview::ScrollDelta::Lines { x: 0.0, y: 1.0 },
+            Modifiers::empty(),
+        );
+        translator.take_raw_input();
+        assert_eq!(translator.scroll_kind(), Some(ScrollKind::Wheel));
+
+        scroll(
+            &mut translator,
+            baseview::ScrollDelta::Pixels { x: 0.0, y: 3.0 },
+            Modifiers::empty(),
+        );
+        translator.take_raw_input();
+        assert_eq!(translator.scroll_kind(), Some(ScrollKind::Smooth));
+
+        translator.take_raw_input();
+        assert_eq!(translator.scroll_kind(), None);
+    }
+
+    #[test]
+    fn momentum_scroll() {
+        let mut translator = translator(2.0);
+
+        translator.on_momentum_scroll(&baseview::ScrollDelta::Pixels { x: 0.0, y: 4.0 });
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![egui::Event::Scroll(vec2(0.0, 2.0))]
+        );
+        translator.take_raw_input();
+        assert_eq!(translator.scroll_kind(), Some(ScrollKind::Momentum));
+    }
+
+    #[test]
+    fn magnify_zooms() {
+        let mut translator = translator(1.0);
+
+        translator.on_magnify(0.25);
+        translator.on_magnify(-1.0);
+
+        assert_eq!(take_events(&mut translator), vec![egui::Event::Zoom(1.25)]);
+    }
+
+    #[test]
+    fn sent_gestures_are_received() {
+        let sender = InputSender::default();
+        sender.send(ExternalInput::MomentumScroll(baseview::ScrollDelta::Pixels {
+            x: 0.0,
+            y: 4.0,
+        }));
+        sender.send(ExternalInput::Magnify(0.25));
+
+        let mut translator = translator(2.0);
+        translator.receive(&sender);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![egui::Event::Scroll(vec2(0.0, 2.0)), egui::Event::Zoom(1.25)]
+        );
+        translator.take_raw_input();
+        assert_eq!(translator.scroll_kind(), Some(ScrollKind::Momentum));
+    }
+
+    #[cfg(feature = "accesskit")]
+    #[test]
+    fn sent_accesskit_actions_are_received() {
+        use egui::accesskit::{Action, ActionRequest, NodeId};
+
+        let request = ActionRequest {
+            action: Action::Default,
+            target: NodeId(std::num::NonZeroU128::new(2).unwrap()),
+            data: None,
+        };
+        let sender = InputSender::default();
+        sender.send(ExternalInput::AccessKitAction(request.clone()));
+
+        let mut translator = translator(1.0);
+        translator.receive(&sender);
+
+        assert_eq!(
+            take_events(&mut translator),
+            vec![egui::Event::AccessKitActionRequest(request)]
+        );
+    }
+}
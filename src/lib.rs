@@ -1,6 +1,85 @@
+/// Create a profiler scope with the `puffin` feature enabled, otherwise do nothing.
+macro_rules! profile_scope {
+    ($($arg: tt)*) => {
+        #[cfg(feature = "puffin")]
+        puffin::profile_scope!($($arg)*);
+    };
+}
+pub(crate) use profile_scope;
+
+/// Profile the current function with the `puffin` feature enabled, otherwise do nothing.
+macro_rules! profile_function {
+    ($($arg: tt)*) => {
+        #[cfg(feature = "puffin")]
+        puffin::profile_function!($($arg)*);
+    };
+}
+pub(crate) use profile_function;
+
+/// Report an error through the `log` facade with the `log` feature enabled, otherwise
+/// print it to stderr. This expands to a single block, so it can also be used as an
+/// expression, for example in a match arm.
+macro_rules! log_error {
+    (target: $target: expr, $($arg: tt)+) => {{
+        #[cfg(feature = "log")]
+        log::error!(target: $target, $($arg)+);
+        #[cfg(not(feature = "log"))]
+        {
+            let _ = $target;
+            eprintln!($($arg)+);
+        }
+    }};
+}
+pub(crate) use log_error;
+
+/// Log targets, so that the diagnostics of each subsystem can be filtered separately.
+pub(crate) mod log_target {
+    pub const CLIPBOARD: &str = "egui_baseview::clipboard";
+    pub const RENDERER: &str = "egui_baseview::renderer";
+    pub const CONTEXT: &str = "egui_baseview::context";
+    pub const DRAG: &str = "egui_baseview::drag";
+    #[cfg(feature = "recording")]
+    pub const RECORDING: &str = "egui_baseview::recording";
+    #[cfg(feature = "persistence")]
+    pub const PERSISTENCE: &str = "egui_baseview::persistence";
+}
+
+#[cfg(feature = "accesskit")]
+pub mod accessibility;
+mod clock;
+mod debug_overlay;
+mod drag;
+mod fonts;
+pub mod input;
+#[cfg(feature = "persistence")]
+pub mod persistence;
+#[cfg(feature = "recording")]
+pub mod recording;
 mod renderer;
+mod shared_context;
+mod stats;
+mod streaming_texture;
+mod tessellation;
+mod theme;
+mod user_textures;
 pub mod window;
 
-pub use window::{EguiWindow, Queue};
-pub use window::{translate_virtual_key_code};
-pub use window::{is_copy_command, is_cut_command, is_paste_command};
\ No newline at end of file
+#[cfg(feature = "accesskit")]
+pub use accessibility::{AccessibilityAdapter, AccessibilityTree};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use debug_overlay::KeyChord;
+pub use drag::{DragPayload, DragSource};
+pub use fonts::FontConfig;
+pub use input::{
+    ClipboardSource, ExternalInput, InputSender, InputTranslator, ScrollKind, TouchEvent,
+};
+#[cfg(feature = "persistence")]
+pub use persistence::PersistedState;
+pub use shared_context::SharedContext;
+pub use stats::{FrameStats, RepaintReason};
+pub use streaming_texture::StreamingTexture;
+pub use tessellation::TessellationMode;
+pub use theme::{Theme, ThemeMode};
+pub use window::{EguiWindow, Queue, MAX_UI_SCALE, MIN_UI_SCALE};
+pub use input::{translate_virtual_key_code};
+pub use input::{is_copy_command, is_cut_command, is_paste_command};
@@ -0,0 +1,111 @@
+//! Saving egui's memory and the window settings between editor sessions.
+//!
+//! Plugin hosts close and reopen editors all the time, which would otherwise lose the
+//! state of collapsing headers, scroll areas, egui windows and the UI scale. Register a
+//! hook with [`crate::Queue::set_persistence_hook`] to receive the state as bytes when
+//! the window closes, store them e.g. in the plugin's state chunk, and restore them in
+//! `build` the next time the editor is opened:
+//!
+//! ```no_run
+//! use std::sync::{Arc, Mutex};
+//!
+//! use baseview::{Size, WindowHandle, WindowOpenOptions, WindowScalePolicy};
+//! use egui_baseview::{EguiWindow, PersistedState, Queue};
+//! use raw_window_handle::HasRawWindowHandle;
+//!
+//! fn open_editor(parent: &impl HasRawWindowHandle, plugin_state: Arc<Mutex<Vec<u8>>>) -> WindowHandle {
+//!     let mut saved = PersistedState::from_bytes(&plugin_state.lock().unwrap()).ok();
+//!
+//!     let mut settings = WindowOpenOptions {
+//!         title: String::from("editor"),
+//!         size: Size::new(400.0, 300.0),
+//!         scale: WindowScalePolicy::SystemScaleFactor,
+//!         gl_config: None,
+//!     };
+//!     if let Some(saved) = &saved {
+//!         settings.size = saved.size();
+//!     }
+//!
+//!     EguiWindow::open_parented(
+//!         parent,
+//!         settings,
+//!         (),
+//!         move |ctx: &egui::Context, queue: &mut Queue, _state: &mut ()| {
+//!             if let Some(saved) = saved.take() {
+//!                 saved.restore(ctx, queue);
+//!             }
+//!
+//!             let plugin_state = Arc::clone(&plugin_state);
+//!             queue.set_persistence_hook(move |bytes| *plugin_state.lock().unwrap() = bytes);
+//!         },
+//!         |ctx: &egui::Context, _queue: &mut Queue, _state: &mut ()| {
+//!             egui::CentralPanel::default().show(ctx, |ui| ui.label("Hello"));
+//!         },
+//!     )
+//! }
+//! ```
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::window::Queue;
+
+/// The state that is saved when the window closes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistedState {
+    pub memory: egui::Memory,
+    /// The UI scale (`1.0` = 100%).
+    pub ui_scale: f32,
+    /// The logical size of the window at a UI scale of 100%.
+    pub size: (f64, f64),
+}
+
+impl PersistedState {
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        ron::de::from_bytes(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        ron::to_string(self)
+            .map(String::into_bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    /// The size to open the window with, see `WindowOpenOptions::size`. The window is
+    /// resized for the UI scale when it is restored.
+    pub fn size(&self) -> baseview::Size {
+        baseview::Size::new(self.size.0, self.size.1)
+    }
+
+    /// Restore the memory and the UI scale. Call this in `build`.
+    pub fn restore(self, egui_ctx: &egui::Context, queue: &mut Queue) {
+        *egui_ctx.memory() = self.memory;
+        queue.set_ui_scale(self.ui_scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let state = PersistedState {
+            memory: egui::Memory::default(),
+            ui_scale: 1.5,
+            size: (640.0, 480.0),
+        };
+
+        let restored = PersistedState::from_bytes(&state.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(restored.ui_scale, 1.5);
+        assert_eq!(restored.size, (640.0, 480.0));
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let err = PersistedState::from_bytes(b"not a state").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,642 @@
+//! Recording of the baseview events an [`crate::EguiWindow`] receives, and deterministic
+//! replay of such a recording without a window.
+//!
+//! A recording is a text file with one RON encoded [`RecordedEntry`] per line, so a
+//! recording that was cut off (e.g. because the host crashed) can still be loaded.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use baseview::{DropData, MouseButton, Point, ScrollDelta};
+use keyboard_types::{KeyboardEvent, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::ManualClock;
+use crate::input::{ExternalInput, InputTranslator, TouchEvent};
+use crate::window::{Queue, QueueState};
+
+/// One line of a recording.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    /// Seconds since the window was opened.
+    pub time: f64,
+    /// egui's `pixels_per_point` when the entry was recorded.
+    pub pixels_per_point: f32,
+    /// The UI scale when the entry was recorded, see [`crate::Queue::set_ui_scale`].
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+    /// The physical size of the window when the entry was recorded.
+    pub physical_size: (u32, u32),
+    pub kind: RecordedKind,
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedKind {
+    /// The window ran a frame.
+    Frame,
+    /// The window received an event.
+    Event(RecordedEvent),
+    /// The window received input through its [`crate::InputSender`].
+    External(RecordedExternalInput),
+}
+
+/// A serializable copy of a [`baseview::Event`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    CursorMoved { x: f64, y: f64, modifiers: Modifiers },
+    ButtonPressed { button: RecordedMouseButton, modifiers: Modifiers },
+    ButtonReleased { button: RecordedMouseButton, modifiers: Modifiers },
+    WheelScrolled { delta: RecordedScrollDelta, modifiers: Modifiers },
+    CursorEntered,
+    CursorLeft,
+    DragEntered { x: f64, y: f64, modifiers: Modifiers, data: RecordedDropData },
+    DragMoved { x: f64, y: f64, modifiers: Modifiers, data: RecordedDropData },
+    DragLeft,
+    DragDropped { x: f64, y: f64, modifiers: Modifiers, data: RecordedDropData },
+    Keyboard(KeyboardEvent),
+    Resized { physical_width: u32, physical_height: u32, scale: f64 },
+    Focused,
+    Unfocused,
+    WillClose,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedMouseButton {
+    Left,
+    Middle,
+    Right,
+    Back,
+    Forward,
+    Other(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedScrollDelta {
+    Lines { x: f32, y: f32 },
+    Pixels { x: f32, y: f32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedDropData {
+    None,
+    Files(Vec<PathBuf>),
+}
+
+/// A serializable copy of an [`ExternalInput`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedExternalInput {
+    Touch {
+        device_id: u64,
+        id: u64,
+        phase: RecordedTouchPhase,
+        x: f64,
+        y: f64,
+        pressure: Option<f32>,
+    },
+    MomentumScroll(RecordedScrollDelta),
+    Magnify(f32),
+    PointerCaptureLost,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedTouchPhase {
+    Start,
+    Move,
+    End,
+    Cancel,
+}
+
+impl RecordedExternalInput {
+    /// Copy an input, `None` if the input is not supported by the recorder.
+    pub fn from_input(input: &ExternalInput) -> Option<Self> {
+        Some(match input {
+            ExternalInput::Touch(event) => RecordedExternalInput::Touch {
+                device_id: event.device_id,
+                id: event.id,
+                phase: event.phase.into(),
+                x: event.position.x,
+                y: event.position.y,
+                pressure: event.pressure,
+            },
+            ExternalInput::MomentumScroll(delta) => {
+                RecordedExternalInput::MomentumScroll((*delta).into())
+            }
+            ExternalInput::Magnify(magnification) => RecordedExternalInput::Magnify(*magnification),
+            ExternalInput::PointerCaptureLost => RecordedExternalInput::PointerCaptureLost,
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        })
+    }
+
+    /// Convert back into the input that was recorded.
+    pub fn to_input(&self) -> ExternalInput {
+        match *self {
+            RecordedExternalInput::Touch {
+                device_id,
+                id,
+                phase,
+                x,
+                y,
+                pressure,
+            } => ExternalInput::Touch(TouchEvent {
+                device_id,
+                id,
+                phase: phase.into(),
+                position: Point::new(x, y),
+                pressure,
+            }),
+            RecordedExternalInput::MomentumScroll(delta) => {
+                ExternalInput::MomentumScroll(delta.into())
+            }
+            RecordedExternalInput::Magnify(magnification) => ExternalInput::Magnify(magnification),
+            RecordedExternalInput::PointerCaptureLost => ExternalInput::PointerCaptureLost,
+        }
+    }
+}
+
+impl RecordedEvent {
+    /// Copy a baseview event, `None` if the event is not supported by the recorder.
+    pub fn from_event(event: &baseview::Event) -> Option<Self> {
+        Some(match event {
+            baseview::Event::Mouse(event) => match event {
+                baseview::MouseEvent::CursorMoved { position, modifiers } => {
+                    RecordedEvent::CursorMoved {
+                        x: position.x,
+                        y: position.y,
+                        modifiers: *modifiers,
+                    }
+                }
+                baseview::MouseEvent::ButtonPressed { button, modifiers } => {
+                    RecordedEvent::ButtonPressed {
+                        button: (*button).into(),
+                        modifiers: *modifiers,
+                    }
+                }
+                baseview::MouseEvent::ButtonReleased { button, modifiers } => {
+                    RecordedEvent::ButtonReleased {
+                        button: (*button).into(),
+                        modifiers: *modifiers,
+                    }
+                }
+                baseview::MouseEvent::WheelScrolled { delta, modifiers } => {
+                    RecordedEvent::WheelScrolled {
+                        delta: (*delta).into(),
+                        modifiers: *modifiers,
+                    }
+                }
+                baseview::MouseEvent::CursorEntered => RecordedEvent::CursorEntered,
+                baseview::MouseEvent::CursorLeft => RecordedEvent::CursorLeft,
+                baseview::MouseEvent::DragEntered {
+                    position,
+                    modifiers,
+                    data,
+                } => RecordedEvent::DragEntered {
+                    x: position.x,
+                    y: position.y,
+                    modifiers: *modifiers,
+                    data: data.into(),
+                },
+                baseview::MouseEvent::DragMoved {
+                    position,
+                    modifiers,
+                    data,
+                } => RecordedEvent::DragMoved {
+                    x: position.x,
+                    y: position.y,
+                    modifiers: *modifiers,
+                    data: data.into(),
+                },
+                baseview::MouseEvent::DragLeft => RecordedEvent::DragLeft,
+                baseview::MouseEvent::DragDropped {
+                    position,
+                    modifiers,
+                    data,
+                } => RecordedEvent::DragDropped {
+                    x: position.x,
+                    y: position.y,
+                    modifiers: *modifiers,
+                    data: data.into(),
+                },
+                #[allow(unreachable_patterns)]
+                _ => return None,
+            },
+            baseview::Event::Keyboard(event) => RecordedEvent::Keyboard(event.clone()),
+            baseview::Event::Window(event) => match event {
+                baseview::WindowEvent::Resized(window_info) => RecordedEvent::Resized {
+                    physical_width: window_info.physical_size().width,
+                    physical_height: window_info.physical_size().height,
+                    scale: window_info.scale(),
+                },
+                baseview::WindowEvent::Focused => RecordedEvent::Focused,
+                baseview::WindowEvent::Unfocused => RecordedEvent::Unfocused,
+                baseview::WindowEvent::WillClose => RecordedEvent::WillClose,
+                #[allow(unreachable_patterns)]
+                _ => return None,
+            },
+        })
+    }
+
+    /// Convert back into the baseview event that was recorded.
+    pub fn to_event(&self) -> baseview::Event {
+        use baseview::{Event, MouseEvent, WindowEvent};
+
+        match self.clone() {
+            RecordedEvent::CursorMoved { x, y, modifiers } => Event::Mouse(MouseEvent::CursorMoved {
+                position: Point::new(x, y),
+                modifiers,
+            }),
+            RecordedEvent::ButtonPressed { button, modifiers } => {
+                Event::Mouse(MouseEvent::ButtonPressed {
+                    button: button.into(),
+                    modifiers,
+                })
+            }
+            RecordedEvent::ButtonReleased { button, modifiers } => {
+                Event::Mouse(MouseEvent::ButtonReleased {
+                    button: button.into(),
+                    modifiers,
+                })
+            }
+            RecordedEvent::WheelScrolled { delta, modifiers } => {
+                Event::Mouse(MouseEvent::WheelScrolled {
+                    delta: delta.into(),
+                    modifiers,
+                })
+            }
+            RecordedEvent::CursorEntered => Event::Mouse(MouseEvent::CursorEntered),
+            RecordedEvent::CursorLeft => Event::Mouse(MouseEvent::CursorLeft),
+            RecordedEvent::DragEntered {
+                x,
+                y,
+                modifiers,
+                data,
+            } => Event::Mouse(MouseEvent::DragEntered {
+                position: Point::new(x, y),
+                modifiers,
+                data: data.into(),
+            }),
+            RecordedEvent::DragMoved {
+                x,
+                y,
+                modifiers,
+                data,
+            } => Event::Mouse(MouseEvent::DragMoved {
+                position: Point::new(x, y),
+                modifiers,
+                data: data.into(),
+            }),
+            RecordedEvent::DragLeft => Event::Mouse(MouseEvent::DragLeft),
+            RecordedEvent::DragDropped {
+                x,
+                y,
+                modifiers,
+                data,
+            } => Event::Mouse(MouseEvent::DragDropped {
+                position: Point::new(x, y),
+                modifiers,
+                data: data.into(),
+            }),
+            RecordedEvent::Keyboard(event) => Event::Keyboard(event),
+            RecordedEvent::Resized {
+                physical_width,
+                physical_height,
+                scale,
+            } => Event::Window(WindowEvent::Resized(baseview::WindowInfo::from_physical_size(
+                baseview::PhySize::new(physical_width, physical_height),
+                scale,
+            ))),
+            RecordedEvent::Focused => Event::Window(WindowEvent::Focused),
+            RecordedEvent::Unfocused => Event::Window(WindowEvent::Unfocused),
+            RecordedEvent::WillClose => Event::Window(WindowEvent::WillClose),
+        }
+    }
+}
+
+impl From<MouseButton> for RecordedMouseButton {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => RecordedMouseButton::Left,
+            MouseButton::Middle => RecordedMouseButton::Middle,
+            MouseButton::Right => RecordedMouseButton::Right,
+            MouseButton::Back => RecordedMouseButton::Back,
+            MouseButton::Forward => RecordedMouseButton::Forward,
+            MouseButton::Other(button) => RecordedMouseButton::Other(button),
+        }
+    }
+}
+
+impl From<RecordedMouseButton> for MouseButton {
+    fn from(button: RecordedMouseButton) -> Self {
+        match button {
+            RecordedMouseButton::Left => MouseButton::Left,
+            RecordedMouseButton::Middle => MouseButton::Middle,
+            RecordedMouseButton::Right => MouseButton::Right,
+            RecordedMouseButton::Back => MouseButton::Back,
+            RecordedMouseButton::Forward => MouseButton::Forward,
+            RecordedMouseButton::Other(button) => MouseButton::Other(button),
+        }
+    }
+}
+
+impl From<egui::TouchPhase> for RecordedTouchPhase {
+    fn from(phase: egui::TouchPhase) -> Self {
+        match phase {
+            egui::TouchPhase::Start => RecordedTouchPhase::Start,
+            egui::TouchPhase::Move => RecordedTouchPhase::Move,
+            egui::TouchPhase::End => RecordedTouchPhase::End,
+            egui::TouchPhase::Cancel => RecordedTouchPhase::Cancel,
+        }
+    }
+}
+
+impl From<RecordedTouchPhase> for egui::TouchPhase {
+    fn from(phase: RecordedTouchPhase) -> Self {
+        match phase {
+            RecordedTouchPhase::Start => egui::TouchPhase::Start,
+            RecordedTouchPhase::Move => egui::TouchPhase::Move,
+            RecordedTouchPhase::End => egui::TouchPhase::End,
+            RecordedTouchPhase::Cancel => egui::TouchPhase::Cancel,
+        }
+    }
+}
+
+impl From<&DropData> for RecordedDropData {
+    fn from(data: &DropData) -> Self {
+        match data {
+            DropData::None => RecordedDropData::None,
+            DropData::Files(paths) => RecordedDropData::Files(paths.clone()),
+        }
+    }
+}
+
+impl From<RecordedDropData> for DropData {
+    fn from(data: RecordedDropData) -> Self {
+        match data {
+            RecordedDropData::None => DropData::None,
+            RecordedDropData::Files(paths) => DropData::Files(paths),
+        }
+    }
+}
+
+impl From<ScrollDelta> for RecordedScrollDelta {
+    fn from(delta: ScrollDelta) -> Self {
+        match delta {
+            ScrollDelta::Lines { x, y } => RecordedScrollDelta::Lines { x, y },
+            ScrollDelta::Pixels { x, y } => RecordedScrollDelta::Pixels { x, y },
+        }
+    }
+}
+
+impl From<RecordedScrollDelta> for ScrollDelta {
+    fn from(delta: RecordedScrollDelta) -> Self {
+        match delta {
+            RecordedScrollDelta::Lines { x, y } => ScrollDelta::Lines { x, y },
+            RecordedScrollDelta::Pixels { x, y } => ScrollDelta::Pixels { x, y },
+        }
+    }
+}
+
+/// Writes a recording to a file, see [`crate::Queue::start_recording`].
+pub(crate) struct EventRecorder {
+    writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn record(&mut self, entry: &RecordedEntry) -> io::Result<()> {
+        let line = ron::to_string(entry).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        writeln!(self.writer, "{}", line)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A recording loaded from a file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventRecording {
+    pub entries: Vec<RecordedEntry>,
+}
+
+impl EventRecording {
+    /// Load a recording written by [`crate::Queue::start_recording`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = ron::from_str(&line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            entries.push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Replay the recording without a window.
+    ///
+    /// The events are translated the same way [`crate::EguiWindow`] does it, and the frames
+    /// run at the recorded times, so the result is deterministic. The clipboard is not
+    /// available during a replay. Returns the output of every frame.
+    pub fn replay<State, U>(
+        &self,
+        egui_ctx: &egui::Context,
+        state: &mut State,
+        mut update: U,
+    ) -> Vec<egui::FullOutput>
+    where
+        U: FnMut(&egui::Context, &mut Queue, &mut State),
+    {
+        let mut input = InputTranslator::default();
+        let mut outputs = Vec::new();
+
+        // Changes requested through the queue have no effect during a replay.
+        let mut queue_state = QueueState::new(Box::new(ManualClock::new()));
+
+        for entry in &self.entries {
+            input.set_pixels_per_point(entry.pixels_per_point);
+            input.set_ui_scale(entry.ui_scale);
+
+            match &entry.kind {
+                RecordedKind::Event(event) => match event.to_event() {
+                    baseview::Event::Mouse(event) => {
+                        input.set_drop_accepted(queue_state.drop_effect.is_some());
+                        input.on_mouse_event(&event);
+                        queue_state.on_drag_event(&event);
+                    }
+                    baseview::Event::Keyboard(event) => input.on_keyboard_event(&event, None),
+                    event @ baseview::Event::Window(_) => {
+                        input.on_event(&event, None);
+                    }
+                },
+                RecordedKind::External(external) => input.on_external_input(&external.to_input()),
+                RecordedKind::Frame => {
+                    input.raw_input_mut().time = Some(entry.time);
+                    input.set_screen(
+                        entry.physical_size.0,
+                        entry.physical_size.1,
+                        entry.pixels_per_point,
+                    );
+
+                    queue_state.apply_pending_fonts(egui_ctx);
+                    egui_ctx.begin_frame(input.take_raw_input());
+
+                    queue_state.scroll_kind = input.scroll_kind();
+                    let mut queue = Queue::new(&mut queue_state);
+                    update(egui_ctx, &mut queue, state);
+
+                    outputs.push(egui_ctx.end_frame());
+                }
+            }
+        }
+
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{pos2, Pos2};
+
+    fn entry(time: f64, kind: RecordedKind) -> RecordedEntry {
+        RecordedEntry {
+            time,
+            pixels_per_point: 1.0,
+            ui_scale: 1.0,
+            physical_size: (400, 300),
+            kind,
+        }
+    }
+
+    fn button(pressed: bool) -> RecordedKind {
+        let button = RecordedMouseButton::Left;
+        let modifiers = Modifiers::empty();
+
+        RecordedKind::Event(if pressed {
+            RecordedEvent::ButtonPressed { button, modifiers }
+        } else {
+            RecordedEvent::ButtonReleased { button, modifiers }
+        })
+    }
+
+    #[test]
+    fn recorded_entries_are_loaded_and_replayed() {
+        let path = std::env::temp_dir().join(format!(
+            "egui-baseview-recording-{}.ron",
+            std::process::id()
+        ));
+
+        let entries = vec![
+            entry(
+                0.0,
+                RecordedKind::Event(RecordedEvent::CursorMoved {
+                    x: 10.0,
+                    y: 20.0,
+                    modifiers: Modifiers::empty(),
+                }),
+            ),
+            entry(0.1, button(true)),
+            entry(0.1, RecordedKind::Frame),
+            entry(0.2, button(false)),
+            entry(0.2, RecordedKind::Frame),
+        ];
+
+        let mut recorder = EventRecorder::create(&path).unwrap();
+        for entry in &entries {
+            recorder.record(entry).unwrap();
+        }
+        recorder.flush().unwrap();
+        drop(recorder);
+
+        let recording = EventRecording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recording.entries, entries);
+
+        // The time and the pointer state egui saw in every frame.
+        let mut frames: Vec<(f64, Option<Pos2>, bool)> = Vec::new();
+        let outputs = recording.replay(&egui::Context::default(), &mut frames, |ctx, _, frames| {
+            let input = ctx.input();
+            frames.push((
+                input.time,
+                input.pointer.interact_pos(),
+                input.pointer.primary_down(),
+            ));
+        });
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(
+            frames,
+            vec![
+                (0.1, Some(pos2(10.0, 20.0)), true),
+                (0.2, Some(pos2(10.0, 20.0)), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn drag_events_are_recorded() {
+        let event = baseview::Event::Mouse(baseview::MouseEvent::DragDropped {
+            position: Point::new(5.0, 6.0),
+            modifiers: Modifiers::empty(),
+            data: DropData::Files(vec![PathBuf::from("/samples/kick.wav")]),
+        });
+
+        let recorded = RecordedEvent::from_event(&event).unwrap();
+        assert_eq!(
+            recorded,
+            RecordedEvent::DragDropped {
+                x: 5.0,
+                y: 6.0,
+                modifiers: Modifiers::empty(),
+                data: RecordedDropData::Files(vec![PathBuf::from("/samples/kick.wav")]),
+            }
+        );
+        assert_eq!(RecordedEvent::from_event(&recorded.to_event()), Some(recorded));
+
+        let left = baseview::Event::Mouse(baseview::MouseEvent::DragLeft);
+        assert_eq!(RecordedEvent::from_event(&left), Some(RecordedEvent::DragLeft));
+    }
+
+    #[test]
+    fn external_input_is_recorded_and_replayed() {
+        let touch = ExternalInput::Touch(TouchEvent {
+            device_id: 1,
+            id: 7,
+            phase: egui::TouchPhase::Start,
+            position: Point::new(10.0, 20.0),
+            pressure: Some(0.5),
+        });
+        let recorded = RecordedExternalInput::from_input(&touch).unwrap();
+        assert_eq!(recorded.to_input(), touch);
+
+        let recording = EventRecording {
+            entries: vec![
+                entry(0.0, RecordedKind::External(RecordedExternalInput::Magnify(0.25))),
+                entry(0.0, RecordedKind::Frame),
+            ],
+        };
+
+        let mut zoom_deltas = Vec::new();
+        recording.replay(&egui::Context::default(), &mut zoom_deltas, |ctx, _, zoom_deltas| {
+            zoom_deltas.push(ctx.input().zoom_delta());
+        });
+        assert_eq!(zoom_deltas, vec![1.25]);
+    }
+}
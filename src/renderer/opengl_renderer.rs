@@ -3,10 +3,15 @@ use egui_glow::Painter;
 use std::sync::Arc;
 
 use std::sync::Mutex;
-use egui::{Id, Rgba};
+use egui::Id;
 use egui_glow::glow;
 use std::ops::Deref;
 use std::borrow::BorrowMut;
+use std::time::Instant;
+
+use crate::tessellation::TessellatedFrame;
+use crate::user_textures::{TextureChanges, TextureUpdate};
+use crate::FrameStats;
 
 
 pub struct Renderer {
@@ -30,7 +35,11 @@ impl Renderer {
 
         let painter = egui_glow::Painter::new(Arc::clone(&glow_context), None, "")
             .map_err(|error| {
-                eprintln!("error occurred in initializing painter:\n{}", error);
+                crate::log_error!(
+                    target: crate::log_target::RENDERER,
+                    "error occurred in initializing painter:\n{}",
+                    error
+                );
             })
             .unwrap();
 
@@ -51,13 +60,21 @@ impl Renderer {
         bg_color: egui::Rgba,
         canvas_width: u32,
         canvas_height: u32,
-        pixels_per_point: f32,
         egui_ctx: &mut egui::Context,
-        shapes: &mut Vec<egui::epaint::ClippedShape>,
-        textures_delta: &mut egui::TexturesDelta,
+        frame: TessellatedFrame,
+        frame_stats: &mut FrameStats,
     ) {
-        let shapes = std::mem::take(shapes);
-        let mut textures_delta = std::mem::take(textures_delta);
+        crate::profile_function!();
+
+        let TessellatedFrame {
+            clipped_primitives,
+            pixels_per_point,
+            textures,
+        } = frame;
+        let TextureChanges {
+            textures_delta,
+            user_textures,
+        } = textures;
 
         let context = window
             .gl_context()
@@ -69,12 +86,11 @@ impl Renderer {
         // BEGIN MODIFIED
         let gl = &self.glow_context;
         // NOTE: We need to clear in sRGB on MacOS, so for simplicity we do that for every platform.
-        let color = Rgba::from_srgba_premultiplied(32, 32, 32, 255); // This happens to be premultiplied already because alpha is 255.
         unsafe {
             use egui_glow::glow::HasContext as _;
             gl.enable(glow::FRAMEBUFFER_SRGB);
             gl.disable(glow::SCISSOR_TEST);
-            gl.clear_color(color[0], color[1], color[2], color[3]);
+            gl.clear_color(bg_color[0], bg_color[1], bg_color[2], bg_color[3]);
             gl.clear_depth_f32(1.0);
             gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
             gl.disable(glow::FRAMEBUFFER_SRGB);
@@ -90,24 +106,43 @@ impl Renderer {
         }
         // END MODIFIED
 
-        for (id, image_delta) in textures_delta.set {
-            self.painter.set_texture(id, &image_delta);
-        }
+        let start = Instant::now();
+        {
+            crate::profile_scope!("paint");
+
+            // Textures are only freed after painting, because the primitives of this frame
+            // may still use them.
+            let mut freed_user_textures = Vec::new();
+            for (id, update) in user_textures {
+                match update {
+                    TextureUpdate::Image(image_delta) => self.painter.set_texture(id, &image_delta),
+                    TextureUpdate::Native(texture) => self.painter.replace_native_texture(id, texture),
+                    TextureUpdate::Free => freed_user_textures.push(id),
+                }
+            }
 
-        let clipped_primitives = egui_ctx.tessellate(shapes);
-        let dimensions: [u32; 2] = [canvas_width, canvas_height];
+            for (id, image_delta) in textures_delta.set {
+                self.painter.set_texture(id, &image_delta);
+            }
+
+            let dimensions: [u32; 2] = [canvas_width, canvas_height];
 
-        self.painter
-            .paint_primitives(dimensions, pixels_per_point, &clipped_primitives);
+            self.painter
+                .paint_primitives(dimensions, pixels_per_point, &clipped_primitives);
 
-        for id in textures_delta.free.drain(..) {
-            self.painter.free_texture(id);
+            for id in textures_delta.free.into_iter().chain(freed_user_textures) {
+                self.painter.free_texture(id);
+            }
         }
+        frame_stats.paint = start.elapsed();
 
+        let start = Instant::now();
         unsafe {
+            crate::profile_scope!("swap_buffers");
             context.swap_buffers();
             context.make_not_current();
         }
+        frame_stats.swap = start.elapsed();
     }
 }
 
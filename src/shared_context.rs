@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use egui::epaint::{ImageData, ImageDelta};
+use egui::{TextureId, TexturesDelta};
+
+/// An [`egui::Context`] that outlives the windows it is used in, see
+/// [`crate::EguiWindow::open_parented_with_context`].
+///
+/// Plugin hosts destroy and recreate editor windows all the time. Keeping the context
+/// keeps egui's memory and the rasterized fonts, so reopening the editor is instant. The
+/// textures have to be uploaded to every new window, so their images are retained here.
+///
+/// The context can only be used by one window at a time. Clones refer to the same context,
+/// so a clone can be kept to reopen the editor after it was closed. Opening a window with
+/// the context while another window still uses it logs an error and opens the window with
+/// a new context instead.
+#[derive(Clone)]
+pub struct SharedContext {
+    egui_ctx: egui::Context,
+    textures: Arc<Mutex<TextureRegistry>>,
+    /// Set while a window uses the context.
+    in_use: Arc<AtomicBool>,
+}
+
+impl SharedContext {
+    pub fn new() -> Self {
+        Self::from_egui_ctx(egui::Context::default())
+    }
+
+    /// Share a context that has not been used in a window yet.
+    pub fn from_egui_ctx(egui_ctx: egui::Context) -> Self {
+        Self {
+            egui_ctx,
+            textures: Arc::new(Mutex::new(TextureRegistry::default())),
+            in_use: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn egui_ctx(&self) -> &egui::Context {
+        &self.egui_ctx
+    }
+
+    /// Whether a window currently uses the context.
+    pub fn is_in_use(&self) -> bool {
+        self.in_use.load(Ordering::Acquire)
+    }
+
+    /// Mark the context as used by a window until the lease is dropped. `None` if another
+    /// window uses it.
+    pub(crate) fn acquire(&self) -> Option<ContextLease> {
+        if self.in_use.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        Some(ContextLease {
+            in_use: Arc::clone(&self.in_use),
+        })
+    }
+
+    pub(crate) fn into_parts(self) -> (egui::Context, Arc<Mutex<TextureRegistry>>) {
+        (self.egui_ctx, self.textures)
+    }
+}
+
+impl Default for SharedContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a [`SharedContext`] marked as in use, see [`SharedContext::acquire`].
+pub(crate) struct ContextLease {
+    in_use: Arc<AtomicBool>,
+}
+
+impl Drop for ContextLease {
+    fn drop(&mut self) {
+        self.in_use.store(false, Ordering::Release);
+    }
+}
+
+/// The full images of all textures egui has allocated, kept up to date from the
+/// [`TexturesDelta`] of every frame.
+#[derive(Default)]
+pub(crate) struct TextureRegistry {
+    textures: HashMap<TextureId, ImageDelta>,
+}
+
+impl TextureRegistry {
+    pub fn apply(&mut self, delta: &TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            match (image_delta.pos, self.textures.get_mut(id)) {
+                (None, _) => {
+                    self.textures.insert(*id, image_delta.clone());
+                }
+                (Some(pos), Some(texture)) => patch_image(&mut texture.image, &image_delta.image, pos),
+                // A partial update of a texture we don't know; nothing to patch.
+                (Some(_), None) => {}
+            }
+        }
+
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    /// The changes that upload every texture to a new painter.
+    pub fn full_delta(&self) -> TexturesDelta {
+        TexturesDelta {
+            set: self
+                .textures
+                .iter()
+                .map(|(id, image_delta)| (*id, image_delta.clone()))
+                .collect(),
+            free: Vec::new(),
+        }
+    }
+}
+
+fn patch_image(target: &mut ImageData, patch: &ImageData, pos: [usize; 2]) {
+    match (target, patch) {
+        (ImageData::Color(target), ImageData::Color(patch)) => copy_rect(
+            &mut target.pixels,
+            target.size[0],
+            &patch.pixels,
+            patch.size,
+            pos,
+        ),
+        (ImageData::Font(target), ImageData::Font(patch)) => copy_rect(
+            &mut target.pixels,
+            target.size[0],
+            &patch.pixels,
+            patch.size,
+            pos,
+        ),
+        _ => {}
+    }
+}
+
+fn copy_rect<T: Copy>(
+    target: &mut [T],
+    target_width: usize,
+    patch: &[T],
+    patch_size: [usize; 2],
+    [x, y]: [usize; 2],
+) {
+    let [width, height] = patch_size;
+    for row in 0..height {
+        let start = (y + row) * target_width + x;
+        if let Some(target_row) = target.get_mut(start..start + width) {
+            target_row.copy_from_slice(&patch[row * width..(row + 1) * width]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{Color32, ColorImage};
+
+    fn image(size: [usize; 2], color: Color32) -> ImageData {
+        ImageData::Color(ColorImage::new(size, color))
+    }
+
+    fn pixels(registry: &TextureRegistry, id: TextureId) -> Vec<Color32> {
+        match &registry.textures[&id].image {
+            ImageData::Color(image) => image.pixels.clone(),
+            ImageData::Font(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn context_is_used_by_one_window_at_a_time() {
+        let context = SharedContext::new();
+        let reopened = context.clone();
+
+        let lease = context.acquire().unwrap();
+        assert!(reopened.is_in_use());
+        assert!(reopened.acquire().is_none());
+
+        drop(lease);
+        assert!(!reopened.is_in_use());
+        assert!(reopened.acquire().is_some());
+    }
+
+    #[test]
+    fn partial_updates_are_patched_into_the_full_image() {
+        let id = TextureId::Managed(1);
+        let mut registry = TextureRegistry::default();
+
+        registry.apply(&TexturesDelta {
+            set: vec![(id, ImageDelta::full(image([2, 2], Color32::BLACK), Default::default()))],
+            free: vec![],
+        });
+        registry.apply(&TexturesDelta {
+            set: vec![(
+                id,
+                ImageDelta::partial([1, 1], image([1, 1], Color32::WHITE), Default::default()),
+            )],
+            free: vec![],
+        });
+
+        assert_eq!(
+            pixels(&registry, id),
+            vec![Color32::BLACK, Color32::BLACK, Color32::BLACK, Color32::WHITE]
+        );
+
+        let full_delta = registry.full_delta();
+        assert_eq!(full_delta.set.len(), 1);
+        assert!(full_delta.set[0].1.is_whole());
+    }
+
+    #[test]
+    fn freed_textures_are_forgotten() {
+        let id = TextureId::Managed(1);
+        let mut registry = TextureRegistry::default();
+
+        registry.apply(&TexturesDelta {
+            set: vec![(id, ImageDelta::full(image([1, 1], Color32::BLACK), Default::default()))],
+            free: vec![],
+        });
+        registry.apply(&TexturesDelta {
+            set: vec![],
+            free: vec![id],
+        });
+
+        assert!(registry.full_delta().set.is_empty());
+    }
+}
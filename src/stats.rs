@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// Timings of a single frame, see [`crate::Queue::frame_stats`].
+///
+/// The rendering timings are zero if the frame was not rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Time spent in the `update` closure.
+    pub user_update: Duration,
+    /// Time spent in `egui::Context::end_frame`.
+    pub end_frame: Duration,
+    /// Time spent tessellating the shapes into meshes. With
+    /// [`crate::TessellationMode::Worker`] this is the time spent waiting for the worker to
+    /// finish the previous frame, which is painted in this one.
+    pub tessellation: Duration,
+    /// Time spent uploading textures and painting the meshes.
+    pub paint: Duration,
+    /// Time spent swapping the buffers.
+    pub swap: Duration,
+    /// The number of bytes of texture data uploaded to the GPU.
+    pub texture_upload_bytes: usize,
+    /// Total time spent in the frame.
+    pub total: Duration,
+    /// Why the frame was rendered, `None` if it was not rendered.
+    pub repaint_reason: Option<RepaintReason>,
+}
+
+/// Why a frame was rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepaintReason {
+    /// The frame received input events.
+    Input,
+    /// egui requested an immediate repaint.
+    Requested,
+    /// A repaint scheduled by egui or by a resize became due.
+    Scheduled,
+}
@@ -0,0 +1,129 @@
+use egui::{pos2, Color32, ColorImage, Rect, TextureFilter, TextureId};
+
+use crate::window::Queue;
+
+/// A texture that is written one column at a time, like a scrolling spectrogram.
+///
+/// The columns are stored in a ring buffer, so every new column only uploads a single
+/// column of pixels instead of the whole image. [`StreamingTexture::paint`] draws the
+/// columns from the oldest to the newest one.
+pub struct StreamingTexture {
+    id: TextureId,
+    size: [usize; 2],
+    /// The column that is written next, which is also the oldest one.
+    write_column: usize,
+}
+
+impl StreamingTexture {
+    /// Register a texture of `size` pixels filled with `background`.
+    pub fn new(
+        queue: &mut Queue,
+        size: [usize; 2],
+        background: Color32,
+        filter: TextureFilter,
+    ) -> Self {
+        let id = queue.register_image(ColorImage::new(size, background), filter);
+
+        Self {
+            id,
+            size,
+            write_column: 0,
+        }
+    }
+
+    pub fn id(&self) -> TextureId {
+        self.id
+    }
+
+    pub fn size(&self) -> [usize; 2] {
+        self.size
+    }
+
+    /// The position of the oldest column in the texture.
+    pub fn offset(&self) -> usize {
+        self.write_column
+    }
+
+    /// Replace the oldest column with `pixels`, from top to bottom.
+    ///
+    /// `pixels` must have as many entries as the texture is high.
+    pub fn push_column(&mut self, queue: &mut Queue, pixels: Vec<Color32>) {
+        assert_eq!(pixels.len(), self.size[1], "column has the wrong height");
+
+        let column = ColorImage {
+            size: [1, self.size[1]],
+            pixels,
+        };
+        queue.update_image_region(self.id, [self.write_column, 0], column);
+
+        self.write_column = (self.write_column + 1) % self.size[0];
+    }
+
+    /// Draw the texture into `rect`, with the oldest column on the left.
+    pub fn paint(&self, painter: &egui::Painter, rect: Rect) {
+        let split = self.write_column as f32 / self.size[0] as f32;
+        let split_x = rect.left() + rect.width() * (1.0 - split);
+
+        // The oldest columns, from the write position to the end of the texture.
+        painter.image(
+            self.id,
+            Rect::from_min_max(rect.min, pos2(split_x, rect.bottom())),
+            Rect::from_min_max(pos2(split, 0.0), pos2(1.0, 1.0)),
+            Color32::WHITE,
+        );
+
+        // The newest columns, from the start of the texture to the write position.
+        if self.write_column > 0 {
+            painter.image(
+                self.id,
+                Rect::from_min_max(pos2(split_x, rect.top()), rect.max),
+                Rect::from_min_max(pos2(0.0, 0.0), pos2(split, 1.0)),
+                Color32::WHITE,
+            );
+        }
+    }
+
+    /// Free the texture. Textures are also freed when the window closes.
+    pub fn free(self, queue: &mut Queue) {
+        queue.free_texture(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{PendingOutput, QueueState};
+    use crate::{FrameStats, ManualClock};
+
+    /// Render the pending output like the window does and return its stats.
+    fn render(state: &mut QueueState) -> FrameStats {
+        let mut pending = PendingOutput::default();
+        pending.push(
+            Vec::new(),
+            egui::TexturesDelta::default(),
+            state.user_textures.take_pending(),
+        );
+
+        let mut frame_stats = FrameStats::default();
+        pending.take(&mut frame_stats);
+        frame_stats
+    }
+
+    #[test]
+    fn pushing_a_column_uploads_one_column() {
+        let mut state = QueueState::new(Box::new(ManualClock::new()));
+        let mut texture = StreamingTexture::new(
+            &mut Queue::new(&mut state),
+            [512, 256],
+            Color32::BLACK,
+            TextureFilter::Nearest,
+        );
+        assert_eq!(render(&mut state).texture_upload_bytes, 512 * 256 * 4);
+
+        texture.push_column(&mut Queue::new(&mut state), vec![Color32::WHITE; 256]);
+        assert_eq!(render(&mut state).texture_upload_bytes, 256 * 4);
+        assert_eq!(texture.offset(), 1);
+
+        assert_eq!(render(&mut state).texture_upload_bytes, 0);
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use egui::epaint::{ClippedPrimitive, ClippedShape};
+
+use crate::user_textures::TextureChanges;
+
+/// Where the shapes of a frame are tessellated, see [`crate::Queue::set_tessellation_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TessellationMode {
+    /// Tessellate right before painting. This is the default.
+    #[default]
+    GuiThread,
+    /// Tessellate on a worker thread while the window waits for the next frame tick, and
+    /// paint the result on that tick. This takes the tessellation out of the GUI thread's
+    /// frame time, but every frame is shown one tick later.
+    Worker,
+}
+
+/// The output of a frame, ready to be painted.
+pub(crate) struct TessellatedFrame {
+    pub clipped_primitives: Vec<ClippedPrimitive>,
+    /// egui's `pixels_per_point` in the frame.
+    pub pixels_per_point: f32,
+    /// The texture changes since the previously painted frame, which the primitives of this
+    /// frame rely on.
+    pub textures: TextureChanges,
+}
+
+struct Job {
+    egui_ctx: egui::Context,
+    shapes: Vec<ClippedShape>,
+    pixels_per_point: f32,
+    textures: TextureChanges,
+}
+
+/// A thread that tessellates one frame at a time.
+pub(crate) struct TessellationWorker {
+    jobs: Option<Sender<Job>>,
+    results: Receiver<TessellatedFrame>,
+    /// Whether a submitted frame has not been received yet.
+    in_flight: bool,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TessellationWorker {
+    pub fn new() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name(String::from("egui-baseview tessellation"))
+            .spawn(move || {
+                for job in job_receiver {
+                    crate::profile_scope!("tessellate");
+                    let frame = TessellatedFrame {
+                        clipped_primitives: job.egui_ctx.tessellate(job.shapes),
+                        pixels_per_point: job.pixels_per_point,
+                        textures: job.textures,
+                    };
+                    if result_sender.send(frame).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn the tessellation thread");
+
+        Self {
+            jobs: Some(jobs),
+            results,
+            in_flight: false,
+            thread: Some(thread),
+        }
+    }
+
+    /// Start tessellating a frame that just ended, together with its texture changes. The
+    /// previous frame has to be received with [`TessellationWorker::wait`] first.
+    ///
+    /// The worker tessellates with the fonts and the scale of the context, so the context
+    /// must not begin a new frame before the result was received.
+    pub fn submit(
+        &mut self,
+        egui_ctx: &egui::Context,
+        shapes: Vec<ClippedShape>,
+        textures: TextureChanges,
+    ) {
+        debug_assert!(!self.in_flight, "the previous frame was not received");
+
+        if let Some(jobs) = &self.jobs {
+            let job = Job {
+                egui_ctx: egui_ctx.clone(),
+                shapes,
+                pixels_per_point: egui_ctx.pixels_per_point(),
+                textures,
+            };
+            self.in_flight = jobs.send(job).is_ok();
+        }
+    }
+
+    /// Wait for the submitted frame, `None` if no frame was submitted.
+    pub fn wait(&mut self) -> Option<TessellatedFrame> {
+        if !std::mem::replace(&mut self.in_flight, false) {
+            return None;
+        }
+
+        crate::profile_function!();
+        self.results.recv().ok()
+    }
+}
+
+impl Drop for TessellationWorker {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread after its current job.
+        self.jobs = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::epaint::Shape;
+    use egui::{pos2, Color32, Rect, Stroke};
+
+    #[test]
+    fn frames_are_received_in_order() {
+        let egui_ctx = egui::Context::default();
+        // The fonts are only available after the first frame.
+        egui_ctx.begin_frame(Default::default());
+        let _ = egui_ctx.end_frame();
+
+        let mut worker = TessellationWorker::new();
+
+        assert!(worker.wait().is_none());
+
+        let shape = ClippedShape(
+            Rect::EVERYTHING,
+            Shape::line_segment([pos2(0.0, 0.0), pos2(10.0, 10.0)], Stroke::new(1.0, Color32::RED)),
+        );
+        worker.submit(&egui_ctx, vec![shape], TextureChanges::default());
+        assert_eq!(worker.wait().unwrap().clipped_primitives.len(), 1);
+
+        let mut textures = TextureChanges::default();
+        textures.textures_delta.free.push(egui::TextureId::Managed(7));
+        worker.submit(&egui_ctx, Vec::new(), textures);
+        let frame = worker.wait().unwrap();
+        assert!(frame.clipped_primitives.is_empty());
+        // The texture changes travel with the frame they belong to.
+        assert_eq!(frame.textures.textures_delta.free, vec![egui::TextureId::Managed(7)]);
+        assert!(worker.wait().is_none());
+    }
+}
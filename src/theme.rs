@@ -0,0 +1,155 @@
+#[cfg(feature = "themes")]
+use std::io;
+#[cfg(feature = "themes")]
+use std::path::Path;
+
+/// Which of the styles of a [`Theme`] is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "themes", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThemeMode {
+    Dark,
+    Light,
+    /// Follow the light/dark preference of the OS. This needs the `system_theme` feature
+    /// and falls back to dark where the preference can't be detected.
+    System,
+}
+
+/// The styles of a window, set with [`crate::Queue::set_theme`].
+///
+/// The window is cleared with the background color of the active style, unless a color
+/// was set with [`crate::Queue::bg_color`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "themes", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    pub dark: egui::Style,
+    pub light: egui::Style,
+    pub mode: ThemeMode,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::with_mode(ThemeMode::Dark)
+    }
+}
+
+impl Theme {
+    /// egui's default dark and light styles.
+    pub fn with_mode(mode: ThemeMode) -> Self {
+        Self {
+            dark: style_with_visuals(egui::Visuals::dark()),
+            light: style_with_visuals(egui::Visuals::light()),
+            mode,
+        }
+    }
+
+    /// The same style in dark and light mode.
+    pub fn from_style(style: egui::Style) -> Self {
+        Self {
+            dark: style.clone(),
+            light: style,
+            mode: ThemeMode::Dark,
+        }
+    }
+
+    /// Load a theme from a RON file, or from a JSON file if its extension is `.json`.
+    #[cfg(feature = "themes")]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+
+        if path.extension().is_some_and(|extension| extension == "json") {
+            Self::from_json(&text)
+        } else {
+            Self::from_ron(&text)
+        }
+    }
+
+    #[cfg(feature = "themes")]
+    pub fn from_ron(text: &str) -> io::Result<Self> {
+        ron::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    #[cfg(feature = "themes")]
+    pub fn from_json(text: &str) -> io::Result<Self> {
+        serde_json::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    #[cfg(feature = "themes")]
+    pub fn to_ron(&self) -> io::Result<String> {
+        ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    #[cfg(feature = "themes")]
+    pub fn to_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    /// The style to use, given whether the OS prefers dark mode.
+    pub(crate) fn style(&self, system_dark: bool) -> &egui::Style {
+        let dark = match self.mode {
+            ThemeMode::Dark => true,
+            ThemeMode::Light => false,
+            ThemeMode::System => system_dark,
+        };
+
+        if dark {
+            &self.dark
+        } else {
+            &self.light
+        }
+    }
+}
+
+fn style_with_visuals(visuals: egui::Visuals) -> egui::Style {
+    egui::Style {
+        visuals,
+        ..Default::default()
+    }
+}
+
+/// Whether the OS prefers dark mode. `true` if that can't be detected.
+pub(crate) fn system_prefers_dark() -> bool {
+    #[cfg(feature = "system_theme")]
+    {
+        !matches!(dark_light::detect(), dark_light::Mode::Light)
+    }
+    #[cfg(not(feature = "system_theme"))]
+    {
+        true
+    }
+}
+
+/// The color to clear the window with for a style.
+pub(crate) fn background_color(style: &egui::Style) -> egui::Rgba {
+    style.visuals.widgets.noninteractive.bg_fill.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_selects_style() {
+        let mut theme = Theme::with_mode(ThemeMode::Light);
+        assert!(!theme.style(true).visuals.dark_mode);
+
+        theme.mode = ThemeMode::System;
+        assert!(theme.style(true).visuals.dark_mode);
+        assert!(!theme.style(false).visuals.dark_mode);
+    }
+
+    #[cfg(feature = "themes")]
+    #[test]
+    fn ron_and_json_round_trip() {
+        let theme = Theme::with_mode(ThemeMode::System);
+
+        let from_ron = Theme::from_ron(&theme.to_ron().unwrap()).unwrap();
+        assert_eq!(from_ron.mode, ThemeMode::System);
+        assert!(!from_ron.light.visuals.dark_mode);
+
+        let from_json = Theme::from_json(&theme.to_json().unwrap()).unwrap();
+        assert_eq!(from_json.mode, ThemeMode::System);
+        assert!(from_json.dark.visuals.dark_mode);
+    }
+}
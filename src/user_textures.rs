@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+
+use egui::epaint::ImageDelta;
+use egui::{TextureFilter, TextureId, TexturesDelta};
+#[cfg(feature = "opengl")]
+use egui_glow::glow;
+
+use crate::log_target;
+
+/// A change of a texture registered through [`crate::Queue`].
+pub(crate) enum TextureUpdate {
+    Image(ImageDelta),
+    #[cfg(feature = "opengl")]
+    Native(glow::Texture),
+    Free,
+}
+
+/// The textures the application registered with the window. The changes are collected here
+/// until the frame they were made in ends, and then travel with its output, see
+/// [`TextureChanges`].
+#[derive(Default)]
+pub(crate) struct UserTextures {
+    next_id: u64,
+    /// The allocated textures that were not freed yet.
+    allocated: HashSet<TextureId>,
+    pending: Vec<(TextureId, TextureUpdate)>,
+    /// The filter of every registered image, which partial updates have to repeat.
+    filters: HashMap<TextureId, TextureFilter>,
+}
+
+impl UserTextures {
+    pub fn allocate(&mut self) -> TextureId {
+        let id = TextureId::User(self.next_id);
+        self.next_id += 1;
+        self.allocated.insert(id);
+        id
+    }
+
+    /// Queue a change of a texture. Changes of textures that were not allocated here or
+    /// that were freed are ignored, because they could e.g. free egui's font texture.
+    pub fn push(&mut self, id: TextureId, update: TextureUpdate) {
+        if !self.allocated.contains(&id) {
+            crate::log_error!(
+                target: log_target::RENDERER,
+                "Ignoring a change of texture {:?}, which was not registered through the queue",
+                id
+            );
+            return;
+        }
+
+        match &update {
+            TextureUpdate::Image(image_delta) if image_delta.is_whole() => {
+                self.filters.insert(id, image_delta.filter);
+            }
+            TextureUpdate::Free => {
+                self.allocated.remove(&id);
+                self.filters.remove(&id);
+            }
+            _ => {}
+        }
+
+        self.pending.push((id, update));
+    }
+
+    /// The filter the image was registered with, `None` for other textures.
+    pub fn filter(&self, id: TextureId) -> Option<TextureFilter> {
+        self.filters.get(&id).copied()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn take_pending(&mut self) -> Vec<(TextureId, TextureUpdate)> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// The texture changes that are applied right before a frame is painted, because that's
+/// when the renderer's GL context is current and the frame's primitives need them.
+#[derive(Default)]
+pub(crate) struct TextureChanges {
+    /// The changes of the textures managed by egui.
+    pub textures_delta: TexturesDelta,
+    /// The changes of the textures registered through [`crate::Queue`], in order.
+    pub user_textures: Vec<(TextureId, TextureUpdate)>,
+}
+
+impl TextureChanges {
+    /// Add the changes that were made after the current ones.
+    pub fn append(&mut self, later: TextureChanges) {
+        self.textures_delta.append(later.textures_delta);
+        self.user_textures.extend(later.user_textures);
+    }
+
+    /// Add the changes that were made before the current ones.
+    pub fn prepend(&mut self, earlier: TextureChanges) {
+        let later = std::mem::replace(self, earlier);
+        self.append(later);
+    }
+
+    /// The number of bytes the painter uploads to apply the changes.
+    pub fn upload_bytes(&self) -> usize {
+        let user_images = self.user_textures.iter().filter_map(|(_, update)| match update {
+            TextureUpdate::Image(image_delta) => Some(image_delta),
+            _ => None,
+        });
+        let managed_images = self.textures_delta.set.iter().map(|(_, image_delta)| image_delta);
+
+        user_images.chain(managed_images).map(upload_bytes).sum()
+    }
+}
+
+/// The number of bytes the painter uploads for an image delta. All images are uploaded as
+/// 8-bit sRGBA, including the font image.
+pub(crate) fn upload_bytes(image_delta: &ImageDelta) -> usize {
+    let [width, height] = image_delta.image.size();
+    width * height * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_not_reused() {
+        let mut textures = UserTextures::default();
+
+        let first = textures.allocate();
+        textures.push(first, TextureUpdate::Free);
+        let second = textures.allocate();
+
+        assert_ne!(first, second);
+        assert!(textures.has_pending());
+        assert_eq!(textures.take_pending().len(), 1);
+        assert!(!textures.has_pending());
+    }
+
+    #[test]
+    fn partial_updates_keep_the_filter() {
+        let mut textures = UserTextures::default();
+        let id = textures.allocate();
+
+        let image = egui::ColorImage::new([4, 4], egui::Color32::BLACK);
+        textures.push(
+            id,
+            TextureUpdate::Image(ImageDelta::full(image, TextureFilter::Nearest)),
+        );
+        assert_eq!(textures.filter(id), Some(TextureFilter::Nearest));
+
+        textures.push(id, TextureUpdate::Free);
+        assert_eq!(textures.filter(id), None);
+    }
+
+    #[test]
+    fn only_registered_textures_are_changed() {
+        let mut textures = UserTextures::default();
+        let id = textures.allocate();
+
+        // egui's own textures, e.g. the font texture, can't be freed through the queue.
+        textures.push(TextureId::Managed(0), TextureUpdate::Free);
+        assert_eq!(id, TextureId::User(0));
+        textures.push(TextureId::User(1), TextureUpdate::Free);
+        assert!(!textures.has_pending());
+
+        textures.push(id, TextureUpdate::Free);
+        textures.push(id, TextureUpdate::Free);
+        assert_eq!(textures.take_pending().len(), 1);
+    }
+
+    #[test]
+    fn column_update_uploads_one_column() {
+        let full = egui::ColorImage::new([512, 256], egui::Color32::BLACK);
+        let column = egui::ColorImage::new([1, 256], egui::Color32::BLACK);
+
+        assert_eq!(
+            upload_bytes(&ImageDelta::full(full, TextureFilter::Linear)),
+            512 * 256 * 4
+        );
+        assert_eq!(
+            upload_bytes(&ImageDelta::partial([7, 0], column, TextureFilter::Linear)),
+            256 * 4
+        );
+    }
+}
@@ -1,55 +1,599 @@
 use baseview::{
-    Event, EventStatus, Window, WindowHandle, WindowHandler, WindowOpenOptions,
-    MouseCursor,
+    DropEffect, Event, EventStatus, Window, WindowHandle, WindowHandler, WindowOpenOptions,
+    WindowScalePolicy, MouseCursor,
 };
 use copypasta::ClipboardProvider;
-use egui::{pos2, vec2, Pos2, Rect, Rgba, CursorIcon, ClipboardMime, ClipboardData};
-use keyboard_types::Modifiers;
+use egui::{Rgba, CursorIcon, ClipboardMime};
 use raw_window_handle::HasRawWindowHandle;
-use std::time::Instant;
-
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::debug_overlay::{DebugOverlay, KeyChord};
+use crate::drag::{DragPayload, DragSource};
+use crate::fonts::FontConfig;
+use crate::input::{ClipboardSource, InputSender, InputTranslator, ScrollKind};
+use crate::log_target;
 use crate::renderer::Renderer;
+use crate::shared_context::{ContextLease, SharedContext, TextureRegistry};
+use crate::tessellation::{TessellatedFrame, TessellationMode, TessellationWorker};
+use crate::theme::{self, Theme, ThemeMode};
+use crate::user_textures::{TextureChanges, TextureUpdate, UserTextures};
+use crate::{FrameStats, RepaintReason};
+#[cfg(feature = "recording")]
+use crate::recording::{
+    EventRecorder, RecordedEntry, RecordedEvent, RecordedExternalInput, RecordedKind,
+};
+#[cfg(feature = "accesskit")]
+use crate::accessibility::{AccessibilityAdapter, AccessibilityTree, WindowAccessibility};
+#[cfg(feature = "persistence")]
+use crate::PersistedState;
+
+/// The active event recorder, see [`Queue::start_recording`].
+#[cfg(feature = "recording")]
+pub(crate) type Recorder = Option<EventRecorder>;
+#[cfg(not(feature = "recording"))]
+pub(crate) type Recorder = ();
+
+/// The accessibility tree of the previous frame, see [`Queue::accessibility_tree`], and
+/// the adapter that exposes it.
+#[cfg(feature = "accesskit")]
+pub(crate) type Accessibility = WindowAccessibility;
+#[cfg(not(feature = "accesskit"))]
+pub(crate) type Accessibility = ();
+
+/// Receives the saved state when the window closes, see [`Queue::set_persistence_hook`].
+#[cfg(feature = "persistence")]
+pub(crate) type PersistenceHook = Option<Box<dyn FnMut(Vec<u8>) + Send>>;
+#[cfg(not(feature = "persistence"))]
+pub(crate) type PersistenceHook = ();
+
+// The input translation used to live in this module.
+pub use crate::input::{
+    is_copy_command, is_cut_command, is_paste_command, translate_modifiers,
+    translate_mouse_button, translate_virtual_key_code, EguiKeyboardInput,
+};
 
-pub struct Queue<'a> {
-    bg_color: &'a mut Rgba,
-    close_requested: &'a mut bool,
+/// The smallest UI scale accepted by [`Queue::set_ui_scale`].
+pub const MIN_UI_SCALE: f32 = 0.25;
+/// The largest UI scale accepted by [`Queue::set_ui_scale`].
+pub const MAX_UI_SCALE: f32 = 4.0;
+/// The amount the UI scale changes per zoom keyboard shortcut.
+const UI_SCALE_STEP: f32 = 0.1;
+
+/// Everything `build` and `update` can change through a [`Queue`]. It is owned by the
+/// window, or by [`crate::recording::EventRecording::replay`] when replaying a recording.
+pub(crate) struct QueueState {
+    /// The clear color set with [`Queue::bg_color`], otherwise the theme background is used.
+    pub bg_color: Option<Rgba>,
+    /// The theme set with [`Queue::set_theme`] during the last `update`.
+    pub pending_theme: Option<Theme>,
+    /// The fonts set with [`Queue::set_font_config`], applied before the next frame.
+    pub pending_fonts: Option<FontConfig>,
+    pub close_requested: bool,
+    /// The UI scale set by the user (`1.0` = 100%).
+    pub ui_scale: f32,
+    pub ui_scale_shortcuts: bool,
+    pub max_fps: Option<f32>,
+    pub idle_throttling: bool,
+    /// The stats of the previous frame.
+    pub frame_stats: FrameStats,
+    pub debug_overlay: DebugOverlay,
+    #[cfg_attr(not(feature = "recording"), allow(dead_code))]
+    pub recorder: Recorder,
+    pub clock: Box<dyn Clock>,
+    /// Whether the files dragged over the window are accepted, set with [`Queue::accept_drop`].
+    pub drop_effect: Option<DropEffect>,
+    pub drag_source: Option<Box<dyn DragSource>>,
+    /// The drag started with [`Queue::start_drag`] during the last `update`.
+    pub pending_drag: Option<DragPayload>,
+    pub focus_requested: bool,
+    /// What caused the scrolling in the current frame.
+    pub scroll_kind: Option<ScrollKind>,
+    #[cfg_attr(not(feature = "accesskit"), allow(dead_code))]
+    pub accessibility: Accessibility,
+    #[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+    pub persistence_hook: PersistenceHook,
+    pub user_textures: UserTextures,
+    pub tessellation_mode: TessellationMode,
+    /// Input for the window that baseview does not report, see [`Queue::input_sender`].
+    pub input_sender: InputSender,
 }
 
-impl<'a> Queue<'a> {
-    pub(crate) fn new(bg_color: &'a mut Rgba, close_requested: &'a mut bool) -> Self {
+impl QueueState {
+    pub fn new(clock: Box<dyn Clock>) -> Self {
         Self {
-            bg_color,
-            //renderer,
-            //repaint_requested,
-            close_requested,
+            bg_color: None,
+            pending_theme: None,
+            pending_fonts: None,
+            close_requested: false,
+            ui_scale: 1.0,
+            ui_scale_shortcuts: false,
+            max_fps: None,
+            idle_throttling: false,
+            frame_stats: FrameStats::default(),
+            debug_overlay: DebugOverlay::default(),
+            recorder: Recorder::default(),
+            clock,
+            drop_effect: None,
+            drag_source: None,
+            pending_drag: None,
+            focus_requested: false,
+            scroll_kind: None,
+            accessibility: Accessibility::default(),
+            persistence_hook: PersistenceHook::default(),
+            user_textures: UserTextures::default(),
+            tessellation_mode: TessellationMode::default(),
+            input_sender: InputSender::default(),
+        }
+    }
+
+    /// Apply the fonts set with [`Queue::set_font_config`]. Call this right before
+    /// `begin_frame`, so they are used from that frame on.
+    pub fn apply_pending_fonts(&mut self, egui_ctx: &egui::Context) {
+        if let Some(fonts) = self.pending_fonts.take() {
+            fonts.apply(egui_ctx);
         }
     }
 
-    /// Set the background color.
+    /// Report the drop decision of `update` for a drag event, `None` for other events.
+    ///
+    /// `update` decides about the drop in the next frame, so the OS gets the decision of the
+    /// previous frame. The decision ends with the drag.
+    pub fn on_drag_event(&mut self, event: &baseview::MouseEvent) -> Option<EventStatus> {
+        let effect = match event {
+            baseview::MouseEvent::DragEntered { .. } | baseview::MouseEvent::DragMoved { .. } => {
+                self.drop_effect
+            }
+            baseview::MouseEvent::DragDropped { .. } => self.drop_effect.take(),
+            baseview::MouseEvent::DragLeft => {
+                self.drop_effect = None;
+                return None;
+            }
+            _ => return None,
+        };
+
+        Some(match effect {
+            Some(effect) => EventStatus::AcceptDrop(effect),
+            None => EventStatus::Ignored,
+        })
+    }
+
+    /// Start the drag requested with [`Queue::start_drag`] during the last `update`.
+    /// Returns whether a drag session was started.
+    pub fn start_pending_drag(&mut self, window: &dyn HasRawWindowHandle) -> bool {
+        let payload = match self.pending_drag.take() {
+            Some(payload) => payload,
+            None => return false,
+        };
+
+        let drag_source = match &mut self.drag_source {
+            Some(drag_source) => drag_source,
+            None => {
+                crate::log_error!(
+                    target: log_target::DRAG,
+                    "Can't start a drag without a drag source, see `Queue::set_drag_source`"
+                );
+                return false;
+            }
+        };
+
+        match drag_source.start_drag(window, payload) {
+            Ok(()) => true,
+            Err(err) => {
+                crate::log_error!(target: log_target::DRAG, "Failed to start a drag: {}", err);
+                false
+            }
+        }
+    }
+}
+
+pub struct Queue<'a> {
+    state: &'a mut QueueState,
+}
+
+impl<'a> Queue<'a> {
+    pub(crate) fn new(state: &'a mut QueueState) -> Self {
+        Self { state }
+    }
+
+    /// Set the color the window is cleared with. By default this is the background color
+    /// of the theme.
     pub fn bg_color(&mut self, bg_color: Rgba) {
-        *self.bg_color = bg_color;
+        self.state.bg_color = Some(bg_color);
+    }
+
+    /// Set the theme, starting with the next frame.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.state.pending_theme = Some(theme);
+    }
+
+    /// Set the fonts of the window. Fonts set in `build` are in place before the first
+    /// frame, later changes are applied before the next frame.
+    pub fn set_font_config(&mut self, fonts: FontConfig) {
+        self.state.pending_fonts = Some(fonts);
     }
 
     /// Close the window.
     pub fn close_window(&mut self) {
-        *self.close_requested = true;
+        self.state.close_requested = true;
+    }
+
+    /// The current UI scale (`1.0` = 100%).
+    pub fn ui_scale(&self) -> f32 {
+        self.state.ui_scale
+    }
+
+    /// Set the UI scale (`1.0` = 100%), which is multiplied with the system scale factor.
+    ///
+    /// The window is resized so that the logical layout stays the same. The value is
+    /// clamped to [`MIN_UI_SCALE`]..=[`MAX_UI_SCALE`] and persists across frames.
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.state.ui_scale = ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    }
+
+    /// Enable or disable the zoom keyboard shortcuts (`Ctrl/Cmd` + `+`, `-` and `0`).
+    ///
+    /// Disabled by default.
+    pub fn ui_scale_shortcuts(&mut self, enabled: bool) {
+        self.state.ui_scale_shortcuts = enabled;
+    }
+
+    /// Limit how often a frame is run. `None` (the default) runs a frame on every
+    /// frame tick of baseview.
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.state.max_fps = max_fps.filter(|max_fps| *max_fps > 0.0);
+    }
+
+    /// Skip running `update` entirely when there is no pending input, no repaint
+    /// scheduled by egui and no repaint requested with [`egui::Context::request_repaint`].
+    ///
+    /// If your UI shows data that changes without user input (e.g. meters), you need to
+    /// call [`egui::Context::request_repaint`] or [`egui::Context::request_repaint_after`]
+    /// when this is enabled. Disabled by default.
+    pub fn idle_throttling(&mut self, enabled: bool) {
+        self.state.idle_throttling = enabled;
+    }
+
+    /// What caused the scrolling in this frame, e.g. to tell a trackpad flick from a
+    /// mouse wheel notch. `None` if nothing was scrolled.
+    pub fn scroll_kind(&self) -> Option<ScrollKind> {
+        self.state.scroll_kind
+    }
+
+    /// Choose where the shapes are tessellated. Defaults to [`TessellationMode::GuiThread`].
+    pub fn set_tessellation_mode(&mut self, mode: TessellationMode) {
+        self.state.tessellation_mode = mode;
+    }
+
+    /// The timings of the previous frame.
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.state.frame_stats
+    }
+
+    /// Show or hide the debug overlay with the frame rate, frame timings, the last
+    /// input events and the current modifiers.
+    pub fn show_debug_overlay(&mut self, visible: bool) {
+        self.state.debug_overlay.visible = visible;
+    }
+
+    /// Set the key chord that toggles the debug overlay. `None` (the default) disables it.
+    pub fn debug_overlay_chord(&mut self, chord: Option<KeyChord>) {
+        self.state.debug_overlay.toggle_chord = chord;
+    }
+
+    /// A handle to pass input that baseview does not report to this window, e.g. touch
+    /// events from a platform specific handler. It can be kept and used from any thread.
+    pub fn input_sender(&self) -> InputSender {
+        self.state.input_sender.clone()
+    }
+
+    /// Ask the OS to give the keyboard focus to the window, e.g. when a text field is
+    /// clicked in a host that doesn't give plugin editors the keyboard focus by default.
+    pub fn request_keyboard_focus(&mut self) {
+        self.state.focus_requested = true;
+    }
+
+    /// Accept the files that are currently dragged over the window, see
+    /// `egui::RawInput::hovered_files`. The OS shows the effect as cursor feedback.
+    ///
+    /// The decision applies to the rest of the drag, or until it is changed again.
+    pub fn accept_drop(&mut self, effect: DropEffect) {
+        self.state.drop_effect = Some(effect);
+    }
+
+    /// Reject the files that are currently dragged over the window. This is the default.
+    pub fn reject_drop(&mut self) {
+        self.state.drop_effect = None;
+    }
+
+    /// Set the [`DragSource`] that starts the OS drag sessions for [`Queue::start_drag`].
+    pub fn set_drag_source(&mut self, drag_source: impl DragSource) {
+        self.state.drag_source = Some(Box::new(drag_source));
+    }
+
+    /// Drag data out of the window, e.g. a rendered audio file into the host's timeline.
+    ///
+    /// Call this while the mouse button is pressed, e.g. when
+    /// `egui::Response::drag_started` is true. The drag is started after `update` with the
+    /// drag source set with [`Queue::set_drag_source`].
+    pub fn start_drag(&mut self, payload: DragPayload) {
+        self.state.pending_drag = Some(payload);
+    }
+
+    /// Replace the clock used for egui's input time and for repaint scheduling.
+    ///
+    /// Call this in `build` to use e.g. a [`crate::ManualClock`] from the first frame on.
+    pub fn set_clock(&mut self, clock: impl Clock) {
+        self.state.clock = Box::new(clock);
+    }
+
+    /// The accessibility tree egui produced in the previous frame.
+    #[cfg(feature = "accesskit")]
+    pub fn accessibility_tree(&self) -> &AccessibilityTree {
+        &self.state.accessibility.tree
+    }
+
+    /// Set the adapter that exposes the accessibility tree to assistive technologies. It
+    /// is attached to the window after the current frame.
+    #[cfg(feature = "accesskit")]
+    pub fn set_accessibility_adapter(&mut self, adapter: impl AccessibilityAdapter) {
+        self.state.accessibility.set_adapter(Box::new(adapter));
+    }
+
+    /// Upload an image, e.g. a waveform rendered by the application, and get the id to
+    /// draw it with, e.g. with [`egui::Image`].
+    ///
+    /// The texture belongs to this window and is freed when the window closes.
+    pub fn register_image(
+        &mut self,
+        image: egui::ColorImage,
+        filter: egui::TextureFilter,
+    ) -> egui::TextureId {
+        let id = self.state.user_textures.allocate();
+        self.update_image(id, image, filter);
+        id
+    }
+
+    /// Replace the image of a texture registered with [`Queue::register_image`].
+    pub fn update_image(
+        &mut self,
+        id: egui::TextureId,
+        image: egui::ColorImage,
+        filter: egui::TextureFilter,
+    ) {
+        self.state.user_textures.push(
+            id,
+            TextureUpdate::Image(egui::epaint::ImageDelta::full(image, filter)),
+        );
+    }
+
+    /// Upload `image` into a part of a texture registered with [`Queue::register_image`],
+    /// with its top left corner at `pos` in pixels. Only the changed part is uploaded,
+    /// e.g. a single column of a scrolling spectrogram, see [`crate::StreamingTexture`].
+    ///
+    /// The region must lie inside the texture.
+    pub fn update_image_region(
+        &mut self,
+        id: egui::TextureId,
+        pos: [usize; 2],
+        image: egui::ColorImage,
+    ) {
+        let filter = self.state.user_textures.filter(id).unwrap_or_default();
+        self.state.user_textures.push(
+            id,
+            TextureUpdate::Image(egui::epaint::ImageDelta::partial(pos, image, filter)),
+        );
+    }
+
+    /// Use a GL texture created by the application, e.g. in a custom paint callback, and
+    /// get the id to draw it with.
+    ///
+    /// The window takes ownership of the texture: it is deleted when it is freed or
+    /// replaced, or when the window closes.
+    #[cfg(feature = "opengl")]
+    pub fn register_native_texture(&mut self, texture: egui_glow::glow::Texture) -> egui::TextureId {
+        let id = self.state.user_textures.allocate();
+        self.update_native_texture(id, texture);
+        id
+    }
+
+    /// Replace the GL texture behind an id registered with
+    /// [`Queue::register_native_texture`], deleting the previous one.
+    #[cfg(feature = "opengl")]
+    pub fn update_native_texture(&mut self, id: egui::TextureId, texture: egui_glow::glow::Texture) {
+        self.state.user_textures.push(id, TextureUpdate::Native(texture));
+    }
+
+    /// Free a texture registered through the queue. The id must not be used afterwards.
+    /// Other ids, e.g. of egui's own textures, are ignored.
+    pub fn free_texture(&mut self, id: egui::TextureId) {
+        self.state.user_textures.push(id, TextureUpdate::Free);
+    }
+
+    /// Set the hook that receives the serialized [`crate::PersistedState`] when the
+    /// window closes, replacing the previous one.
+    #[cfg(feature = "persistence")]
+    pub fn set_persistence_hook(&mut self, hook: impl FnMut(Vec<u8>) + Send + 'static) {
+        self.state.persistence_hook = Some(Box::new(hook));
+    }
+
+    /// Start recording every event the window receives to a file, replacing any active
+    /// recording. Load it with [`crate::recording::EventRecording::load`].
+    #[cfg(feature = "recording")]
+    pub fn start_recording(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.state.recorder = Some(EventRecorder::create(path.as_ref())?);
+        Ok(())
+    }
+
+    /// Stop the active recording.
+    #[cfg(feature = "recording")]
+    pub fn stop_recording(&mut self) {
+        if let Some(mut recorder) = self.state.recorder.take() {
+            if let Err(err) = recorder.flush() {
+                crate::log_error!(
+                    target: log_target::RECORDING,
+                    "Failed to write recording: {}",
+                    err
+                );
+            }
+        }
     }
 }
 
 struct OpenSettings {
-    pub physical_width: f64,
-    pub physical_height: f64,
+    pub logical_width: f64,
+    pub logical_height: f64,
+    /// The scale factor requested with [`WindowScalePolicy::ScaleFactor`].
+    pub scale_factor: Option<f64>,
 }
 
 impl OpenSettings {
     fn new(settings: &WindowOpenOptions) -> Self {
+        let scale_factor = match settings.scale {
+            WindowScalePolicy::ScaleFactor(scale_factor) => Some(scale_factor),
+            WindowScalePolicy::SystemScaleFactor => None,
+        };
+
         Self {
-            physical_width: settings.size.width as f64,
-            physical_height: settings.size.height as f64,
+            logical_width: settings.size.width,
+            logical_height: settings.size.height,
+            scale_factor,
+        }
+    }
+
+    /// The scale factor of a [`WindowScalePolicy::ScaleFactor`] policy.
+    ///
+    /// `None` with [`WindowScalePolicy::SystemScaleFactor`], because baseview only reports
+    /// the system scale factor with the first `Resized` event.
+    fn fixed_scale_factor(&self) -> Option<f64> {
+        self.scale_factor
+    }
+
+    /// The physical size of the window, rounded the same way baseview does it. `None`
+    /// until the scale factor is known.
+    fn physical_size(&self) -> Option<(u32, u32)> {
+        let scale_factor = self.fixed_scale_factor()?;
+        Some((
+            (self.logical_width * scale_factor).round() as u32,
+            (self.logical_height * scale_factor).round() as u32,
+        ))
+    }
+}
+
+/// How many ticks the window waits for baseview to report the system scale factor before
+/// it goes on with a scale factor of `1.0`, for hosts that never send the first `Resized`.
+const MAX_SCALE_WAIT_TICKS: u32 = 30;
+
+/// Count down a tick of waiting for the system scale factor, see [`MAX_SCALE_WAIT_TICKS`].
+/// Returns whether the window still waits.
+fn wait_for_scale(ticks_left: &mut Option<u32>) -> bool {
+    match ticks_left {
+        Some(0) => {
+            *ticks_left = None;
+            false
         }
+        Some(ticks) => {
+            *ticks -= 1;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether the next frame may run at a frame rate of at most `max_fps`. With a rate so low
+/// that the frame interval can't be represented, no further frame is run.
+fn frame_interval_elapsed(max_fps: f32, last_frame: Instant, now: Instant) -> bool {
+    Duration::try_from_secs_f32(1.0 / max_fps)
+        .ok()
+        .and_then(|interval| last_frame.checked_add(interval))
+        .is_some_and(|next_frame| now >= next_frame)
+}
+
+/// egui's input time: the seconds since `start_time`, according to `clock`.
+pub(crate) fn input_time(clock: &dyn Clock, start_time: Instant) -> f64 {
+    clock.now().saturating_duration_since(start_time).as_secs_f64()
+}
+
+/// Decide whether the output of a frame that finished at `now` has to be rendered now.
+///
+/// `deadline` is when the output that is already pending has to be rendered. It is moved
+/// to `now` by input or an immediate repaint request, and otherwise to the repaint time
+/// egui asked for, unless an earlier repaint is already scheduled. Returns why the output
+/// has to be rendered now and clears the deadline, or `None` if it can wait.
+pub(crate) fn schedule_repaint(
+    deadline: &mut Option<Instant>,
+    now: Instant,
+    has_input: bool,
+    repaint_after: Duration,
+) -> Option<RepaintReason> {
+    if has_input || repaint_after.is_zero() {
+        *deadline = Some(now);
+    } else if let Some(requested) = now.checked_add(repaint_after) {
+        *deadline = Some(deadline.map_or(requested, |t| t.min(requested)));
+    }
+
+    if !deadline.is_some_and(|t| now >= t) {
+        return None;
+    }
+    *deadline = None;
+
+    Some(if has_input {
+        RepaintReason::Input
+    } else if repaint_after.is_zero() {
+        RepaintReason::Requested
+    } else {
+        RepaintReason::Scheduled
+    })
+}
+
+/// The output of the frames that have not been rendered yet.
+#[derive(Default)]
+pub(crate) struct PendingOutput {
+    /// The shapes of the most recent frame.
+    pub shapes: Vec<egui::epaint::ClippedShape>,
+    /// The texture changes of all frames since the last render, in order.
+    pub textures: TextureChanges,
+}
+
+impl PendingOutput {
+    /// Add the output of a frame. The shapes always describe the whole frame, so only the
+    /// latest ones are kept. Texture changes are incremental and must all reach the
+    /// painter, so they are accumulated until the next render.
+    pub fn push(
+        &mut self,
+        shapes: Vec<egui::epaint::ClippedShape>,
+        textures_delta: egui::TexturesDelta,
+        user_textures: Vec<(egui::TextureId, TextureUpdate)>,
+    ) {
+        self.shapes = shapes;
+        self.textures.append(TextureChanges {
+            textures_delta,
+            user_textures,
+        });
     }
+
+    /// Take the output to render it, recording the bytes its texture changes upload in
+    /// `frame_stats`.
+    pub fn take(
+        &mut self,
+        frame_stats: &mut FrameStats,
+    ) -> (Vec<egui::epaint::ClippedShape>, TextureChanges) {
+        let PendingOutput { shapes, textures } = std::mem::take(self);
+        frame_stats.texture_upload_bytes = textures.upload_bytes();
+        (shapes, textures)
+    }
+}
+
+/// Lock a mutex, ignoring that another thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
 }
 
+/// The `build` closure, which runs before the first frame.
+type BuildFn<State> = Box<dyn FnOnce(&egui::Context, &mut Queue, &mut State) + Send>;
+
 /// Handles an egui-baseview application
 pub struct EguiWindow<State, U>
 where
@@ -58,21 +602,47 @@ where
     U: 'static + Send,
 {
     user_state: Option<State>,
+    /// Taken when the first frame runs.
+    user_build: Option<BuildFn<State>>,
     user_update: U,
 
     egui_ctx: egui::Context,
-    egui_input: egui::RawInput,
+    input: InputTranslator,
     clipboard_ctx: Option<copypasta::ClipboardContext>,
 
     renderer: Renderer,
+    /// The scale factor of the window without the UI scale: the one of the scale policy, or
+    /// the one reported by the system.
+    window_scale_factor: f32,
+    /// The ticks left to wait for baseview to report the system scale factor, `None` once
+    /// it is known. Nothing is built, updated or rendered before that, because egui would
+    /// lay out the UI at the wrong scale.
+    scale_wait_ticks: Option<u32>,
+    /// `window_scale_factor * ui_scale`, this is used as egui's `pixels_per_point`.
     scale_factor: f32,
-    bg_color: Rgba,
+    /// The state shared with [`Queue`].
+    queue_state: QueueState,
+    theme: Option<Theme>,
+    /// The last detected OS preference, for [`ThemeMode::System`].
+    system_dark: bool,
     physical_width: u32,
     physical_height: u32,
+    /// The time of `clock` at which the window was opened.
     start_time: Instant,
+    /// When the pending output has to be rendered.
     repaint_after: Option<Instant>,
-    mouse_pos: Option<Pos2>,
-    close_requested: bool,
+    pending_output: PendingOutput,
+    /// The textures of a [`SharedContext`], to be uploaded again by the next window.
+    texture_registry: Option<Arc<Mutex<TextureRegistry>>>,
+    /// Keeps the [`SharedContext`] marked as in use until the window is dropped.
+    _context_lease: Option<ContextLease>,
+    last_frame: Option<Instant>,
+    /// When egui wants `update` to run again, regardless of input.
+    next_update: Option<Instant>,
+    /// Set when [`egui::Context::request_repaint`] is called, possibly from another thread.
+    repaint_requested: Arc<AtomicBool>,
+    /// Only exists while tessellating in [`TessellationMode::Worker`].
+    tessellation_worker: Option<TessellationWorker>,
     last_cursor_icon: Option<CursorIcon>,
 }
 
@@ -85,27 +655,47 @@ where
     fn new<B>(
         window: &mut baseview::Window<'_>,
         open_settings: OpenSettings,
-        mut build: B,
+        shared_context: Option<SharedContext>,
+        build: B,
         update: U,
-        mut state: State,
+        state: State,
     ) -> EguiWindow<State, U>
     where
         B: FnMut(&egui::Context, &mut Queue, &mut State),
         B: 'static + Send,
     {
-        let egui_ctx = egui::Context::default();
-
-        let guessed_scale = 1.0; // This is a wild guess. After we received some message, we'll know better.
+        let shared_context = shared_context.and_then(|shared_context| {
+            match shared_context.acquire() {
+                Some(lease) => Some((shared_context, lease)),
+                None => {
+                    crate::log_error!(
+                        target: log_target::CONTEXT,
+                        "The SharedContext is used by another window, using a new context"
+                    );
+                    None
+                }
+            }
+        });
+        let (egui_ctx, texture_registry, context_lease) = match shared_context {
+            Some((shared_context, lease)) => {
+                let (egui_ctx, texture_registry) = shared_context.into_parts();
+                (egui_ctx, Some(texture_registry), Some(lease))
+            }
+            None => (egui::Context::default(), None, None),
+        };
+        #[cfg(feature = "accesskit")]
+        egui_ctx.enable_accesskit();
+
+        let repaint_requested = Arc::new(AtomicBool::new(false));
+        {
+            let repaint_requested = Arc::clone(&repaint_requested);
+            egui_ctx.set_request_repaint_callback(move || {
+                repaint_requested.store(true, Ordering::Release);
+            });
+        }
 
+        // `screen_rect` and `pixels_per_point` are set by `update_screen_rect` below.
         let egui_input = egui::RawInput {
-            screen_rect: Some(Rect::from_min_size(
-                Pos2::new(0f32, 0f32),
-                vec2(
-                    open_settings.physical_width as f32,
-                    open_settings.physical_height as f32,
-                ),
-            )),
-            pixels_per_point: Some(guessed_scale),
             modifiers: egui::Modifiers {
                 alt: false,
                 ctrl: false,
@@ -116,50 +706,96 @@ where
             ..Default::default()
         };
 
-        let physical_width = open_settings.physical_width.round() as u32;
-        let physical_height = open_settings.physical_height.round() as u32;
+        // Placeholders until the first `Resized` event if the scale factor is not known yet.
+        let window_scale_factor = open_settings.fixed_scale_factor().unwrap_or(1.0) as f32;
+        let (physical_width, physical_height) = open_settings.physical_size().unwrap_or((
+            open_settings.logical_width.round() as u32,
+            open_settings.logical_height.round() as u32,
+        ));
 
         let renderer = Renderer::new(window);
 
-        let mut bg_color = Rgba::BLACK;
-        let mut close_requested = false;
-        let mut queue = Queue::new(
-            &mut bg_color,
-            //&mut renderer,
-            //&mut repaint_requested,
-            &mut close_requested,
-        );
-        (build)(&egui_ctx, &mut queue, &mut state);
+        let queue_state = QueueState::new(Box::new(SystemClock));
 
         let clipboard_ctx = match copypasta::ClipboardContext::new() {
             Ok(clipboard_ctx) => Some(clipboard_ctx),
             Err(e) => {
-                eprintln!("Failed to initialize clipboard: {}", e);
+                crate::log_error!(
+                    target: log_target::CLIPBOARD,
+                    "Failed to initialize clipboard: {}",
+                    e
+                );
                 None
             }
         };
 
         Self {
             user_state: Some(state),
+            user_build: Some(Box::new(build)),
             user_update: update,
 
             egui_ctx,
-            egui_input,
+            input: InputTranslator::new(egui_input),
             clipboard_ctx,
 
             renderer,
-            scale_factor: guessed_scale,
-            bg_color,
+            window_scale_factor,
+            scale_wait_ticks: match open_settings.fixed_scale_factor() {
+                Some(_) => None,
+                None => Some(MAX_SCALE_WAIT_TICKS),
+            },
+            scale_factor: window_scale_factor * queue_state.ui_scale,
+            theme: None,
+            system_dark: true,
             physical_width,
             physical_height,
-            start_time: Instant::now(),
-            repaint_after: Some(Instant::now()),
-            mouse_pos: None,
-            close_requested,
+            start_time: queue_state.clock.now(),
+            repaint_after: Some(queue_state.clock.now()),
+            // A reused context doesn't send the textures it already sent to the previous
+            // window again, so they are uploaded from the registry.
+            pending_output: PendingOutput {
+                shapes: Vec::new(),
+                textures: TextureChanges {
+                    textures_delta: texture_registry
+                        .as_ref()
+                        .map(|textures| lock(textures).full_delta())
+                        .unwrap_or_default(),
+                    user_textures: Vec::new(),
+                },
+            },
+            texture_registry,
+            _context_lease: context_lease,
+            last_frame: None,
+            next_update: Some(queue_state.clock.now()),
+            repaint_requested,
+            queue_state,
+            tessellation_worker: None,
             last_cursor_icon: None,
         }
     }
 
+    /// Run the `build` closure and apply what it changed.
+    fn build(&mut self, window: &mut Window, build: BuildFn<State>) {
+        if let Some(state) = &mut self.user_state {
+            let mut queue = Queue::new(&mut self.queue_state);
+            build(&self.egui_ctx, &mut queue, state);
+        }
+
+        // `build` may have replaced the clock.
+        let now = self.queue_state.clock.now();
+        self.start_time = now;
+        self.next_update = Some(now);
+
+        self.apply_pending_theme();
+
+        // The window was opened at the size for a UI scale of 100%.
+        if self.queue_state.ui_scale != 1.0 {
+            self.apply_ui_scale(window, 1.0);
+        } else {
+            self.update_screen_rect();
+        }
+    }
+
     /// Open a new child window.
     ///
     /// * `parent` - The parent window.
@@ -191,7 +827,41 @@ where
             parent,
             settings,
             move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
-                EguiWindow::new(window, open_settings, build, update, state)
+                EguiWindow::new(window, open_settings, None, build, update, state)
+            },
+        )
+    }
+
+    /// Open a new child window that uses an existing egui context, so that egui's memory,
+    /// fonts and textures carry over from the previous window that used it.
+    ///
+    /// The context can only be used by one window at a time, see [`SharedContext`].
+    ///
+    /// See [`EguiWindow::open_parented`] for the other arguments.
+    pub fn open_parented_with_context<P, B>(
+        parent: &P,
+        mut settings: WindowOpenOptions,
+        context: SharedContext,
+        state: State,
+        build: B,
+        update: U,
+    ) -> WindowHandle
+    where
+        P: HasRawWindowHandle,
+        B: FnMut(&egui::Context, &mut Queue, &mut State),
+        B: 'static + Send,
+    {
+        if settings.gl_config.is_none() {
+            settings.gl_config = Some(Default::default());
+        }
+
+        let open_settings = OpenSettings::new(&settings);
+
+        Window::open_parented(
+            parent,
+            settings,
+            move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
+                EguiWindow::new(window, open_settings, Some(context), build, update, state)
             },
         )
     }
@@ -223,7 +893,7 @@ where
         Window::open_as_if_parented(
             settings,
             move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
-                EguiWindow::new(window, open_settings, build, update, state)
+                EguiWindow::new(window, open_settings, None, build, update, state)
             },
         )
     }
@@ -250,16 +920,263 @@ where
         Window::open_blocking(
             settings,
             move |window: &mut baseview::Window<'_>| -> EguiWindow<State, U> {
-                EguiWindow::new(window, open_settings, build, update, state)
+                EguiWindow::new(window, open_settings, None, build, update, state)
             },
         )
     }
 
-    /// Update the pressed key modifiers when a mouse event has sent a new set of modifiers.
-    fn update_modifiers(&mut self, modifiers: &Modifiers) {
-        self.egui_input.modifiers.alt = !(*modifiers & Modifiers::ALT).is_empty();
-        self.egui_input.modifiers.shift = !(*modifiers & Modifiers::SHIFT).is_empty();
-        self.egui_input.modifiers.command = !(*modifiers & Modifiers::CONTROL).is_empty();
+    /// Recompute `pixels_per_point` and the logical screen rect from the physical size
+    /// and the current system scale factor and UI scale.
+    fn update_screen_rect(&mut self) {
+        self.scale_factor = self.window_scale_factor * self.queue_state.ui_scale;
+
+        self.input
+            .set_screen(self.physical_width, self.physical_height, self.scale_factor);
+        self.input.set_ui_scale(self.queue_state.ui_scale);
+
+        // Schedule to repaint on the next frame.
+        self.repaint_after = Some(self.queue_state.clock.now());
+    }
+
+    /// Seconds since the window was opened, according to `clock`.
+    fn time(&self) -> f64 {
+        input_time(self.queue_state.clock.as_ref(), self.start_time)
+    }
+
+    /// Write an entry to the active recording, if there is one.
+    #[cfg(feature = "recording")]
+    fn record(&mut self, time: f64, kind: RecordedKind) {
+        if let Some(recorder) = &mut self.queue_state.recorder {
+            let entry = RecordedEntry {
+                time,
+                pixels_per_point: self.scale_factor,
+                ui_scale: self.queue_state.ui_scale,
+                physical_size: (self.physical_width, self.physical_height),
+                kind,
+            };
+
+            if let Err(err) = recorder.record(&entry) {
+                crate::log_error!(
+                    target: log_target::RECORDING,
+                    "Failed to write recording, stopping it: {}",
+                    err
+                );
+                self.queue_state.recorder = None;
+            }
+        }
+    }
+
+    /// Whether a frame should be run now, taking the frame-rate cap and idle throttling
+    /// into account.
+    fn should_run_frame(&mut self, now: Instant) -> bool {
+        if let (Some(max_fps), Some(last_frame)) = (self.queue_state.max_fps, self.last_frame) {
+            if !frame_interval_elapsed(max_fps, last_frame, now) {
+                return false;
+            }
+        }
+
+        if !self.queue_state.idle_throttling {
+            return true;
+        }
+
+        let has_pending_input = !self.input.egui_input.events.is_empty()
+            || self.input.egui_input.screen_rect.is_some()
+            || self.input.egui_input.pixels_per_point.is_some()
+            || !self.input.egui_input.hovered_files.is_empty()
+            || !self.input.egui_input.dropped_files.is_empty();
+        let update_due = self.next_update.is_some_and(|t| now >= t);
+        let repaint_requested = self.repaint_requested.swap(false, Ordering::AcqRel);
+
+        has_pending_input || update_due || repaint_requested
+    }
+
+    /// Apply a changed UI scale by resizing the window so that the logical layout stays the same.
+    fn apply_ui_scale(&mut self, window: &mut Window, previous_ui_scale: f32) {
+        let ratio = (self.queue_state.ui_scale / previous_ui_scale) as f64;
+
+        // The physical size is only updated by the `Resized` event, because some hosts
+        // don't allow the editor to resize itself.
+        window.resize(baseview::Size::new(
+            self.physical_width as f64 * ratio / self.window_scale_factor as f64,
+            self.physical_height as f64 * ratio / self.window_scale_factor as f64,
+        ));
+
+        // Apply the new scale to the current size right away, so it also takes effect if
+        // the window isn't resized.
+        self.update_screen_rect();
+    }
+
+    /// Handle the zoom keyboard shortcuts. Returns `true` if the event was consumed.
+    fn handle_ui_scale_shortcut(
+        &mut self,
+        window: &mut Window,
+        event: &keyboard_types::KeyboardEvent,
+    ) -> bool {
+        use keyboard_types::Code;
+
+        if !self.queue_state.ui_scale_shortcuts || event.state != keyboard_types::KeyState::Down {
+            return false;
+        }
+
+        let modifiers = translate_modifiers(&event.modifiers);
+        if !modifiers.command {
+            return false;
+        }
+
+        let ui_scale = match event.code {
+            Code::Equal | Code::NumpadAdd => self.queue_state.ui_scale + UI_SCALE_STEP,
+            Code::Minus | Code::NumpadSubtract => self.queue_state.ui_scale - UI_SCALE_STEP,
+            Code::Digit0 | Code::Numpad0 => 1.0,
+            _ => return false,
+        };
+
+        let previous_ui_scale = self.queue_state.ui_scale;
+        self.queue_state.ui_scale = ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        if self.queue_state.ui_scale != previous_ui_scale {
+            self.apply_ui_scale(window, previous_ui_scale);
+        }
+
+        true
+    }
+
+    /// Tessellate the pending output and return the frame to paint now. With
+    /// [`TessellationMode::Worker`] the output is tessellated in the background and painted
+    /// on the next tick, so `previous_frame`, the one submitted on the last tick, is
+    /// returned instead.
+    fn tessellate(
+        &mut self,
+        previous_frame: Option<TessellatedFrame>,
+        frame_stats: &mut FrameStats,
+    ) -> Option<TessellatedFrame> {
+        crate::profile_function!();
+
+        let start = Instant::now();
+        let frame = match self.queue_state.tessellation_mode {
+            TessellationMode::GuiThread => {
+                // After switching away from the worker, its last frame is replaced by this
+                // one, but its texture changes still have to be applied first.
+                if let Some(previous_frame) = previous_frame {
+                    self.pending_output.textures.prepend(previous_frame.textures);
+                }
+                self.tessellation_worker = None;
+
+                let (shapes, textures) = self.pending_output.take(frame_stats);
+                Some(TessellatedFrame {
+                    clipped_primitives: self.egui_ctx.tessellate(shapes),
+                    pixels_per_point: self.egui_ctx.pixels_per_point(),
+                    textures,
+                })
+            }
+            TessellationMode::Worker => {
+                let (shapes, textures) = self.pending_output.take(frame_stats);
+                self.tessellation_worker
+                    .get_or_insert_with(TessellationWorker::new)
+                    .submit(&self.egui_ctx, shapes, textures);
+                previous_frame
+            }
+        };
+        frame_stats.tessellation += start.elapsed();
+
+        frame
+    }
+
+    /// Receive the frame that was tessellated in the background since the last tick, if
+    /// any. This has to happen before egui begins a new frame, because the worker
+    /// tessellates with the context's fonts and scale.
+    fn receive_tessellated_frame(&mut self, frame_stats: &mut FrameStats) -> Option<TessellatedFrame> {
+        let start = Instant::now();
+        let frame = self
+            .tessellation_worker
+            .as_mut()
+            .and_then(TessellationWorker::wait);
+        frame_stats.tessellation = start.elapsed();
+
+        frame
+    }
+
+    fn paint(&mut self, window: &mut Window, frame: TessellatedFrame, frame_stats: &mut FrameStats) {
+        let bg_color = self
+            .queue_state
+            .bg_color
+            .unwrap_or_else(|| theme::background_color(&self.egui_ctx.style()));
+
+        self.renderer.render(
+            window,
+            bg_color,
+            self.physical_width,
+            self.physical_height,
+            &mut self.egui_ctx,
+            frame,
+            frame_stats,
+        );
+    }
+
+    fn apply_pending_theme(&mut self) {
+        if let Some(theme) = self.queue_state.pending_theme.take() {
+            self.theme = Some(theme);
+            self.apply_theme();
+        }
+    }
+
+    fn apply_theme(&mut self) {
+        if let Some(theme) = &self.theme {
+            if theme.mode == ThemeMode::System {
+                self.system_dark = theme::system_prefers_dark();
+            }
+
+            self.egui_ctx.set_style(theme.style(self.system_dark).clone());
+            self.egui_ctx.request_repaint();
+        }
+    }
+
+    fn follow_system_theme(&mut self) {
+        let follows_system = self
+            .theme
+            .as_ref()
+            .is_some_and(|theme| theme.mode == ThemeMode::System);
+
+        if follows_system && theme::system_prefers_dark() != self.system_dark {
+            self.apply_theme();
+        }
+    }
+
+    /// Pass the state to the persistence hook. This only happens once, because the hook is
+    /// consumed.
+    #[cfg(feature = "persistence")]
+    fn save_persisted_state(&mut self) {
+        if let Some(mut hook) = self.queue_state.persistence_hook.take() {
+            let state = PersistedState {
+                memory: self.egui_ctx.memory().clone(),
+                ui_scale: self.queue_state.ui_scale,
+                size: (
+                    self.physical_width as f64 / self.scale_factor as f64,
+                    self.physical_height as f64 / self.scale_factor as f64,
+                ),
+            };
+
+            match state.to_bytes() {
+                Ok(bytes) => hook(bytes),
+                Err(err) => crate::log_error!(
+                    target: log_target::PERSISTENCE,
+                    "Failed to save state: {}",
+                    err
+                ),
+            }
+        }
+    }
+}
+
+// Not every platform sends `WillClose` when the host destroys the window, so the state is
+// also saved when the window handler is dropped.
+#[cfg(feature = "persistence")]
+impl<State, U> Drop for EguiWindow<State, U>
+where
+    State: 'static + Send,
+    U: FnMut(&egui::Context, &mut Queue, &mut State),
+    U: 'static + Send,
+{
+    fn drop(&mut self) {
+        self.save_persisted_state();
     }
 }
 
@@ -270,56 +1187,128 @@ where
     U: 'static + Send,
 {
     fn on_frame(&mut self, window: &mut Window) {
+        crate::profile_function!();
+
+        if wait_for_scale(&mut self.scale_wait_ticks) {
+            return;
+        }
+
+        if let Some(build) = self.user_build.take() {
+            self.build(window, build);
+        }
+
+        for input in self.queue_state.input_sender.take() {
+            #[cfg(feature = "recording")]
+            if let Some(recorded_input) = RecordedExternalInput::from_input(&input) {
+                let time = self.time();
+                self.record(time, RecordedKind::External(recorded_input));
+            }
+
+            self.input.on_external_input(&input);
+        }
+
+        let now = self.queue_state.clock.now();
+        if !self.should_run_frame(now) {
+            let mut frame_stats = FrameStats::default();
+            if let Some(frame) = self.receive_tessellated_frame(&mut frame_stats) {
+                self.paint(window, frame, &mut frame_stats);
+            }
+            return;
+        }
+        self.last_frame = Some(now);
+
+        let frame_start = Instant::now();
+        let mut frame_stats = FrameStats::default();
+        let previous_frame = self.receive_tessellated_frame(&mut frame_stats);
+
+        let time = self.time();
+
+        #[cfg(feature = "recording")]
+        self.record(time, RecordedKind::Frame);
+
         if let Some(state) = &mut self.user_state {
-            self.egui_input.time = Some(self.start_time.elapsed().as_nanos() as f64 * 1e-9);
-            self.egui_ctx.begin_frame(self.egui_input.take());
-
-            //let mut repaint_requested = false;
-            let mut queue = Queue::new(
-                &mut self.bg_color,
-                //&mut self.renderer,
-                //&mut repaint_requested,
-                &mut self.close_requested,
-            );
+            let has_input = !self.input.egui_input.events.is_empty();
+            self.queue_state
+                .debug_overlay
+                .record_input(&mut self.input.egui_input.events);
 
-            (self.user_update)(&self.egui_ctx, &mut queue, state);
+            self.input.egui_input.time = Some(time);
 
+            self.queue_state.apply_pending_fonts(&self.egui_ctx);
+            self.egui_ctx.begin_frame(self.input.take_raw_input());
+
+            let previous_ui_scale = self.queue_state.ui_scale;
+
+            self.queue_state.scroll_kind = self.input.scroll_kind();
+            let mut queue = Queue::new(&mut self.queue_state);
+
+            let start = Instant::now();
+            {
+                crate::profile_scope!("user_update");
+                (self.user_update)(&self.egui_ctx, &mut queue, state);
+            }
+            frame_stats.user_update = start.elapsed();
+
+            self.apply_pending_theme();
+
+            if self.queue_state.user_textures.has_pending() {
+                self.egui_ctx.request_repaint();
+            }
+
+            self.queue_state.debug_overlay.show(&self.egui_ctx, &self.queue_state.frame_stats);
+
+            let start = Instant::now();
             let egui::FullOutput {
                 platform_output,
                 repaint_after,
-                mut textures_delta,
-                mut shapes,
-            } = self.egui_ctx.end_frame();
-
-            let now = Instant::now();
-            let do_repaint_now = if let Some(t) = self.repaint_after {
-                now >= t || repaint_after.is_zero()
-            } else {
-                repaint_after.is_zero()
+                textures_delta,
+                shapes,
+            } = {
+                crate::profile_scope!("end_frame");
+                self.egui_ctx.end_frame()
             };
+            frame_stats.end_frame = start.elapsed();
 
-            if do_repaint_now {
-                self.renderer.render(
-                    window,
-                    self.bg_color,
-                    self.physical_width,
-                    self.physical_height,
-                    self.scale_factor,
-                    &mut self.egui_ctx,
-                    &mut shapes,
-                    &mut textures_delta,
-                );
+            if let Some(textures) = &self.texture_registry {
+                lock(textures).apply(&textures_delta);
+            }
+            self.pending_output.push(
+                shapes,
+                textures_delta,
+                self.queue_state.user_textures.take_pending(),
+            );
+
+            let now = self.queue_state.clock.now();
+            self.next_update = now.checked_add(repaint_after);
 
-                self.repaint_after = None;
-            } else if let Some(repaint_after) = now.checked_add(repaint_after) {
-                // Schedule to repaint after the requested time has elapsed.
-                self.repaint_after = Some(repaint_after);
+            frame_stats.repaint_reason =
+                schedule_repaint(&mut self.repaint_after, now, has_input, repaint_after);
+            if frame_stats.repaint_reason.is_some() {
+                if let Some(frame) = self.tessellate(previous_frame, &mut frame_stats) {
+                    self.paint(window, frame, &mut frame_stats);
+                }
+            } else if let Some(frame) = previous_frame {
+                self.paint(window, frame, &mut FrameStats::default());
+            }
+
+            #[cfg(feature = "accesskit")]
+            {
+                if let Some(update) = platform_output.accesskit_update {
+                    self.queue_state.accessibility.update(update);
+                }
+                self.queue_state
+                    .accessibility
+                    .attach_adapter(&*window, &self.queue_state.input_sender);
             }
 
             if !platform_output.copied_text.is_empty() {
                 if let Some(clipboard_ctx) = &mut self.clipboard_ctx {
                     if let Err(err) = clipboard_ctx.set_contents(platform_output.copied_text) {
-                        eprintln!("Copy/Cut error: {}", err);
+                        crate::log_error!(
+                            target: log_target::CLIPBOARD,
+                            "Copy/Cut error: {}",
+                            err
+                        );
                     }
                 }
             }
@@ -327,7 +1316,11 @@ where
             if let Some(egui::ClipboardData { data, mime: ClipboardMime::Specific(mime)}) = platform_output.copied_data {
                 if let Some(clipboard_ctx) = &mut self.clipboard_ctx {
                     if let Err(err) = clipboard_ctx.set_mime_contents(data, &mime) {
-                        eprintln!("Copy/Cut error: {}", err);
+                        crate::log_error!(
+                            target: log_target::CLIPBOARD,
+                            "Copy/Cut error: {}",
+                            err
+                        );
                     }
                 }
             }
@@ -340,321 +1333,96 @@ where
                 window.set_mouse_cursor(translate_cursor_icon(platform_output.cursor_icon));
             }
 
-            if self.close_requested {
-                window.close();
+            if self.queue_state.ui_scale != previous_ui_scale {
+                self.apply_ui_scale(window, previous_ui_scale);
             }
-        }
-    }
 
-    fn on_event(&mut self, _window: &mut Window, event: Event) -> EventStatus {
-        match &event {
-            baseview::Event::Mouse(event) => match event {
-                baseview::MouseEvent::CursorMoved {
-                    position,
-                    modifiers,
-                } => {
-                    self.update_modifiers(modifiers);
-
-                    let pos = pos2(position.x as f32, position.y as f32);
-                    self.mouse_pos = Some(pos);
-                    self.egui_input.events.push(egui::Event::PointerMoved(pos));
-                }
-                baseview::MouseEvent::ButtonPressed { button, modifiers } => {
-                    self.update_modifiers(modifiers);
-
-                    if let Some(pos) = self.mouse_pos {
-                        if let Some(button) = translate_mouse_button(*button) {
-                            self.egui_input.events.push(egui::Event::PointerButton {
-                                pos,
-                                button,
-                                pressed: true,
-                                modifiers: self.egui_input.modifiers,
-                            });
-                        }
-                    }
-                }
-                baseview::MouseEvent::ButtonReleased { button, modifiers } => {
-                    self.update_modifiers(modifiers);
-
-                    if let Some(pos) = self.mouse_pos {
-                        if let Some(button) = translate_mouse_button(*button) {
-                            self.egui_input.events.push(egui::Event::PointerButton {
-                                pos,
-                                button,
-                                pressed: false,
-                                modifiers: self.egui_input.modifiers,
-                            });
-                        }
-                    }
-                }
-                baseview::MouseEvent::WheelScrolled {
-                    delta: scroll_delta,
-                    modifiers,
-                } => {
-                    self.update_modifiers(modifiers);
-
-                    let delta = match scroll_delta {
-                        baseview::ScrollDelta::Lines { x, y } => {
-                            let points_per_scroll_line = 50.0; // Scroll speed decided by consensus: https://github.com/emilk/egui/issues/461
-                            egui::vec2(*x, *y) * points_per_scroll_line
-                        }
-                        baseview::ScrollDelta::Pixels { x, y } => {
-                            if let Some(pixels_per_point) = self.egui_input.pixels_per_point {
-                                egui::vec2(*x, *y) / pixels_per_point
-                            } else {
-                                egui::vec2(*x, *y)
-                            }
-                        }
-                    };
-
-                    if self.egui_input.modifiers.ctrl || self.egui_input.modifiers.command {
-                        // Treat as zoom instead:
-                        let factor = (delta.y / 200.0).exp();
-                        self.egui_input.events.push(egui::Event::Zoom(factor));
-                    } else if self.egui_input.modifiers.shift {
-                        // Treat as horizontal scrolling.
-                        // Note: one Mac we already get horizontal scroll events when shift is down.
-                        self.egui_input
-                            .events
-                            .push(egui::Event::Scroll(egui::vec2(delta.x + delta.y, 0.0)));
-                    } else {
-                        self.egui_input.events.push(egui::Event::Scroll(delta));
-                    }
-                }
-                baseview::MouseEvent::CursorLeft => {
-                    self.mouse_pos = None;
-                    self.egui_input.events.push(egui::Event::PointerGone);
-                }
-                _ => {}
-            },
-            baseview::Event::Keyboard(event) => {
-                EguiKeyboardInput::from_keyboard_event(&event, self.clipboard_ctx.as_mut())
-                    .apply_on_raw_input(&mut self.egui_input);
+            if self.queue_state.start_pending_drag(&*window) {
+                // The OS owns the pointer during the drag, so the button that started it is
+                // not released in this window.
+                self.input.on_pointer_capture_lost();
             }
-            baseview::Event::Window(event) => match event {
-                baseview::WindowEvent::Resized(window_info) => {
-                    self.scale_factor = window_info.scale() as f32;
-
-                    let logical_size = (
-                        (window_info.physical_size().width as f32 / self.scale_factor),
-                        (window_info.physical_size().height as f32 / self.scale_factor),
-                    );
-
-                    self.physical_width = window_info.physical_size().width;
-                    self.physical_height = window_info.physical_size().height;
 
-                    self.egui_input.pixels_per_point = Some(self.scale_factor);
+            if self.queue_state.focus_requested {
+                self.queue_state.focus_requested = false;
+                window.focus();
+            }
 
-                    self.egui_input.screen_rect = Some(Rect::from_min_size(
-                        Pos2::new(0f32, 0f32),
-                        vec2(logical_size.0, logical_size.1),
-                    ));
+            if self.queue_state.close_requested {
+                window.close();
+            }
 
-                    // Schedule to repaint on the next frame.
-                    self.repaint_after = Some(Instant::now());
-                }
-                baseview::WindowEvent::WillClose => {}
-                _ => {}
-            },
+            frame_stats.total = frame_start.elapsed();
+            self.queue_state.debug_overlay.record_frame(frame_start, &frame_stats);
+            self.queue_state.frame_stats = frame_stats;
         }
-
-        EventStatus::Captured
     }
-}
 
-pub struct EguiKeyboardInput {
-    events: Vec<egui::Event>,
-    modifiers: egui::Modifiers,
-}
-impl EguiKeyboardInput {
-    pub fn from_keyboard_event(event: &keyboard_types::KeyboardEvent, clipboard_ctx: Option<&mut copypasta::ClipboardContext>) -> EguiKeyboardInput {
-        let mut events = vec![];
-        let mut modifiers = translate_modifiers(&event.modifiers);
-
-        use keyboard_types::Code;
-
-        let pressed = event.state == keyboard_types::KeyState::Down;
+    fn on_event(&mut self, window: &mut Window, event: Event) -> EventStatus {
+        #[cfg(feature = "recording")]
+        if let Some(recorded_event) = RecordedEvent::from_event(&event) {
+            let time = self.time();
+            self.record(time, RecordedKind::Event(recorded_event));
+        }
 
-        match event.code {
-            Code::ShiftLeft | Code::ShiftRight => modifiers.shift = pressed,
-            Code::ControlLeft | Code::ControlRight => {
-                modifiers.ctrl = pressed;
+        match &event {
+            baseview::Event::Mouse(event) => {
+                self.input.set_drop_accepted(self.queue_state.drop_effect.is_some());
+                self.input.on_mouse_event(event);
 
-                #[cfg(not(target_os = "macos"))]
-                {
-                    modifiers.command = pressed;
+                if let Some(status) = self.queue_state.on_drag_event(event) {
+                    return status;
                 }
             }
-            Code::AltLeft | Code::AltRight => modifiers.alt = pressed,
-            Code::MetaLeft | Code::MetaRight => {
-                #[cfg(target_os = "macos")]
-                {
-                    modifiers.mac_cmd = pressed;
-                    modifiers.command = pressed;
+            baseview::Event::Keyboard(event) => {
+                if self.handle_ui_scale_shortcut(window, event) {
+                    return EventStatus::Captured;
                 }
-                () // prevent `rustfmt` from breaking this
+
+                let clipboard = self
+                    .clipboard_ctx
+                    .as_mut()
+                    .map(|clipboard_ctx| clipboard_ctx as &mut dyn ClipboardSource);
+                self.input.on_keyboard_event(event, clipboard);
             }
-            _ => (),
-        }
+            baseview::Event::Window(event) => match event {
+                baseview::WindowEvent::Resized(window_info) => {
+                    self.window_scale_factor = window_info.scale() as f32;
+                    self.scale_wait_ticks = None;
 
-        if let Some(key) = translate_virtual_key_code(event.code) {
-            events.push(egui::Event::Key { key, pressed, modifiers });
-        }
+                    self.physical_width = window_info.physical_size().width;
+                    self.physical_height = window_info.physical_size().height;
 
-        if pressed {
-            // VirtualKeyCode::Paste etc in winit are broken/untrustworthy,
-            // so we detect these things manually:
-            if is_cut_command(modifiers, event.code) {
-                events.push(egui::Event::Cut);
-            } else if is_copy_command(modifiers, event.code) {
-                events.push(egui::Event::Copy);
-            } else if is_paste_command(modifiers, event.code) {
-                if let Some(clipboard_ctx) = clipboard_ctx {
-                    if let Ok(contents) = clipboard_ctx.get_contents() {
-                        events.push(egui::Event::Paste(contents));
-                    }
-                    if let Ok(data) = clipboard_ctx.get_mime_contents("application/dspstudio") {
-                        events.push(
-                            egui::Event::PasteMime(ClipboardData {
-                                data,
-                                mime: ClipboardMime::Specific("application/dspstudio".to_string())
-                            })
-                        );
-                    }
-                }
-            } else if let keyboard_types::Key::Character(written) = &event.key {
-                if !modifiers.ctrl && !modifiers.command {
-                    events.push(egui::Event::Text(written.clone()));
+                    self.update_screen_rect();
                 }
-            }
-        }
-        EguiKeyboardInput {
-            events,
-            modifiers
-        }
-    }
+                baseview::WindowEvent::Focused | baseview::WindowEvent::Unfocused => {
+                    self.input
+                        .on_focus_changed(matches!(event, baseview::WindowEvent::Focused));
+
+                    // There is no event for changes of the OS preference, so check whenever
+                    // the user comes back to the window.
+                    if matches!(event, baseview::WindowEvent::Focused) {
+                        self.follow_system_theme();
+                    }
 
-    pub fn apply_on_input(self, input_mut: &mut egui::InputState) {
-        for event in self.events {
-            if let egui::Event::Key { key, pressed, .. } = &event {
-                if *pressed {
-                    input_mut.keys_down.insert(*key);
-                } else {
-                    input_mut.keys_down.remove(key);
+                    // Run a frame even without other input, so egui learns about the change.
+                    let now = self.queue_state.clock.now();
+                    self.next_update = Some(now);
+                    self.repaint_after = Some(now);
                 }
-            }
-            input_mut.raw.events.push(event.clone());
-            input_mut.events.push(event);
+                baseview::WindowEvent::WillClose => {
+                    #[cfg(feature = "persistence")]
+                    self.save_persisted_state();
+                }
+                #[allow(unreachable_patterns)]
+                _ => {}
+            },
         }
-        input_mut.raw.modifiers = self.modifiers;
-        input_mut.modifiers = self.modifiers;
-    }
 
-    pub fn apply_on_raw_input(self, raw_input_mut: &mut egui::RawInput) {
-        for event in self.events {
-            raw_input_mut.events.push(event.clone());
-        }
-        raw_input_mut.modifiers = self.modifiers;
-    }
-}
-
-pub fn translate_mouse_button(button: baseview::MouseButton) -> Option<egui::PointerButton> {
-    match button {
-        baseview::MouseButton::Left => Some(egui::PointerButton::Primary),
-        baseview::MouseButton::Right => Some(egui::PointerButton::Secondary),
-        baseview::MouseButton::Middle => Some(egui::PointerButton::Middle),
-        _ => None,
+        EventStatus::Captured
     }
 }
 
-pub fn translate_virtual_key_code(key: keyboard_types::Code) -> Option<egui::Key> {
-    use egui::Key;
-    use keyboard_types::Code;
-
-    Some(match key {
-        Code::ArrowDown => Key::ArrowDown,
-        Code::ArrowLeft => Key::ArrowLeft,
-        Code::ArrowRight => Key::ArrowRight,
-        Code::ArrowUp => Key::ArrowUp,
-
-        Code::Escape => Key::Escape,
-        Code::Tab => Key::Tab,
-        Code::Backspace => Key::Backspace,
-        Code::Enter | Code::NumpadEnter => Key::Enter,
-        Code::Space => Key::Space,
-
-        Code::Insert => Key::Insert,
-        Code::Delete => Key::Delete,
-        Code::Home => Key::Home,
-        Code::End => Key::End,
-        Code::PageUp => Key::PageUp,
-        Code::PageDown => Key::PageDown,
-
-        Code::Digit0 | Code::Numpad0 => Key::Num0,
-        Code::Digit1 | Code::Numpad1 => Key::Num1,
-        Code::Digit2 | Code::Numpad2 => Key::Num2,
-        Code::Digit3 | Code::Numpad3 => Key::Num3,
-        Code::Digit4 | Code::Numpad4 => Key::Num4,
-        Code::Digit5 | Code::Numpad5 => Key::Num5,
-        Code::Digit6 | Code::Numpad6 => Key::Num6,
-        Code::Digit7 | Code::Numpad7 => Key::Num7,
-        Code::Digit8 | Code::Numpad8 => Key::Num8,
-        Code::Digit9 | Code::Numpad9 => Key::Num9,
-
-        Code::KeyA => Key::A,
-        Code::KeyB => Key::B,
-        Code::KeyC => Key::C,
-        Code::KeyD => Key::D,
-        Code::KeyE => Key::E,
-        Code::KeyF => Key::F,
-        Code::KeyG => Key::G,
-        Code::KeyH => Key::H,
-        Code::KeyI => Key::I,
-        Code::KeyJ => Key::J,
-        Code::KeyK => Key::K,
-        Code::KeyL => Key::L,
-        Code::KeyM => Key::M,
-        Code::KeyN => Key::N,
-        Code::KeyO => Key::O,
-        Code::KeyP => Key::P,
-        Code::KeyQ => Key::Q,
-        Code::KeyR => Key::R,
-        Code::KeyS => Key::S,
-        Code::KeyT => Key::T,
-        Code::KeyU => Key::U,
-        Code::KeyV => Key::V,
-        Code::KeyW => Key::W,
-        Code::KeyX => Key::X,
-        Code::KeyY => Key::Y,
-        Code::KeyZ => Key::Z,
-        _ => {
-            return None;
-        }
-    })
-}
-
-pub fn is_cut_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
-    (modifiers.command && keycode == keyboard_types::Code::KeyX)
-        || (cfg!(target_os = "windows")
-            && modifiers.shift
-            && keycode == keyboard_types::Code::Delete)
-}
-
-pub fn is_copy_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
-    (modifiers.command && keycode == keyboard_types::Code::KeyC)
-        || (cfg!(target_os = "windows")
-            && modifiers.ctrl
-            && keycode == keyboard_types::Code::Insert)
-}
-
-pub fn is_paste_command(modifiers: egui::Modifiers, keycode: keyboard_types::Code) -> bool {
-    (modifiers.command && keycode == keyboard_types::Code::KeyV)
-        || (cfg!(target_os = "windows")
-            && modifiers.shift
-            && keycode == keyboard_types::Code::Insert)
-}
-
-
 fn translate_cursor_icon(icon: CursorIcon) -> MouseCursor {
     match icon {
         CursorIcon::Default => MouseCursor::Default,
@@ -695,13 +1463,213 @@ fn translate_cursor_icon(icon: CursorIcon) -> MouseCursor {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::logical_screen_rect;
+    use crate::ManualClock;
+    use baseview::Size;
+    use egui::{vec2, Pos2};
+
+    fn open_settings(width: f64, height: f64, scale: WindowScalePolicy) -> OpenSettings {
+        OpenSettings::new(&WindowOpenOptions {
+            title: String::from("test"),
+            size: Size::new(width, height),
+            scale,
+            gl_config: None,
+        })
+    }
+
+    #[test]
+    fn fixed_scale_factor_is_known_before_the_first_resize() {
+        let settings = open_settings(400.0, 200.0, WindowScalePolicy::ScaleFactor(2.0));
+
+        assert_eq!(settings.fixed_scale_factor(), Some(2.0));
+        assert_eq!(settings.physical_size(), Some((800, 400)));
+    }
+
+    #[test]
+    fn system_scale_factor_is_unknown_until_resized() {
+        let settings = open_settings(400.0, 200.0, WindowScalePolicy::SystemScaleFactor);
+
+        // The window waits for the first `Resized` event instead of guessing.
+        assert_eq!(settings.fixed_scale_factor(), None);
+        assert_eq!(settings.physical_size(), None);
+    }
+
+    #[test]
+    fn frame_rate_cap_handles_tiny_rates() {
+        let last_frame = Instant::now();
+        let later = last_frame + Duration::from_millis(150);
+
+        assert!(frame_interval_elapsed(10.0, last_frame, later));
+        assert!(!frame_interval_elapsed(5.0, last_frame, later));
+        // The interval doesn't fit in a `Duration`.
+        assert!(!frame_interval_elapsed(1e-20, last_frame, later));
+    }
+
+    #[test]
+    fn waiting_for_the_system_scale_factor_times_out() {
+        let mut ticks_left = Some(2);
+        assert!(wait_for_scale(&mut ticks_left));
+        assert!(wait_for_scale(&mut ticks_left));
+        // The host never sent `Resized`, go on with the guess.
+        assert!(!wait_for_scale(&mut ticks_left));
+        assert_eq!(ticks_left, None);
+        assert!(!wait_for_scale(&mut ticks_left));
+    }
+
+    #[test]
+    fn physical_size_is_rounded() {
+        let settings = open_settings(301.0, 111.0, WindowScalePolicy::ScaleFactor(1.25));
+
+        assert_eq!(settings.physical_size(), Some((376, 139)));
+    }
+
+    #[test]
+    fn screen_rect_is_in_points() {
+        let settings = open_settings(400.0, 200.0, WindowScalePolicy::ScaleFactor(1.5));
+        let (width, height) = settings.physical_size().unwrap();
+        let rect = logical_screen_rect(width, height, 1.5);
+
+        assert_eq!(rect.min, Pos2::ZERO);
+        assert_eq!(rect.size(), vec2(400.0, 200.0));
+    }
+
+    #[test]
+    fn ui_scale_shrinks_screen_rect() {
+        let rect = logical_screen_rect(800, 400, 2.0 * 1.25);
+
+        assert_eq!(rect.size(), vec2(320.0, 160.0));
+    }
+
+    #[test]
+    fn repaint_waits_for_the_earliest_deadline() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut deadline = None;
+
+        assert_eq!(
+            schedule_repaint(&mut deadline, clock.now(), false, Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(deadline, Some(start + Duration::from_millis(100)));
+
+        // A later request doesn't postpone the pending repaint.
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(
+            schedule_repaint(&mut deadline, clock.now(), false, Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(deadline, Some(start + Duration::from_millis(100)));
+
+        // Neither does a frame that doesn't need a repaint at all.
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(
+            schedule_repaint(&mut deadline, clock.now(), false, Duration::MAX),
+            Some(RepaintReason::Scheduled)
+        );
+        assert_eq!(deadline, None);
+    }
+
+    #[test]
+    fn input_and_requests_repaint_immediately() {
+        let clock = ManualClock::new();
+
+        let mut deadline = Some(clock.now() + Duration::from_secs(1));
+        assert_eq!(
+            schedule_repaint(&mut deadline, clock.now(), true, Duration::from_secs(1)),
+            Some(RepaintReason::Input)
+        );
+
+        assert_eq!(
+            schedule_repaint(&mut None, clock.now(), false, Duration::ZERO),
+            Some(RepaintReason::Requested)
+        );
+        assert_eq!(
+            schedule_repaint(&mut None, clock.now(), false, Duration::MAX),
+            None
+        );
+    }
+
+    #[test]
+    fn texture_changes_are_coalesced_until_rendered() {
+        use egui::epaint::{ClippedShape, ImageDelta};
+
+        let shape = || ClippedShape(egui::Rect::EVERYTHING, egui::Shape::Noop);
+        let image = |color| {
+            ImageDelta::full(egui::ColorImage::new([1, 1], color), Default::default())
+        };
+        let (a, b) = (egui::TextureId::Managed(1), egui::TextureId::Managed(2));
+
+        let mut pending = PendingOutput::default();
+        pending.push(
+            vec![shape(), shape()],
+            egui::TexturesDelta {
+                set: vec![(a, image(egui::Color32::BLACK))],
+                free: vec![],
+            },
+            Vec::new(),
+        );
+        pending.push(
+            vec![shape()],
+            egui::TexturesDelta {
+                set: vec![(a, image(egui::Color32::WHITE))],
+                free: vec![b],
+            },
+            Vec::new(),
+        );
+
+        let mut frame_stats = FrameStats::default();
+        let (shapes, textures) = pending.take(&mut frame_stats);
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(textures.textures_delta.set.len(), 2);
+        assert_eq!(textures.textures_delta.set[1].0, a);
+        assert_eq!(textures.textures_delta.free, vec![b]);
+        assert_eq!(frame_stats.texture_upload_bytes, 2 * 4);
+        assert!(pending.take(&mut frame_stats).1.textures_delta.set.is_empty());
+        assert_eq!(frame_stats.texture_upload_bytes, 0);
+    }
+
+    #[test]
+    fn drags_are_started_through_the_drag_source() {
+        struct NoWindow;
+
+        unsafe impl HasRawWindowHandle for NoWindow {
+            fn raw_window_handle(&self) -> raw_window_handle::RawWindowHandle {
+                unreachable!("the test drag source doesn't use the window")
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct TestDragSource(Arc<Mutex<Vec<DragPayload>>>);
+
+        impl DragSource for TestDragSource {
+            fn start_drag(
+                &mut self,
+                _window: &dyn HasRawWindowHandle,
+                payload: DragPayload,
+            ) -> std::io::Result<()> {
+                lock(&self.0).push(payload);
+                Ok(())
+            }
+        }
 
-pub fn translate_modifiers(modifiers: &keyboard_types::Modifiers) -> egui::Modifiers {
-    egui::Modifiers {
-        alt: modifiers.contains(keyboard_types::Modifiers::ALT),
-        command: modifiers.contains(keyboard_types::Modifiers::META) || (!cfg!(target_os = "macos") && modifiers.contains(keyboard_types::Modifiers::CONTROL)),
-        ctrl: modifiers.contains(keyboard_types::Modifiers::CONTROL) || (!cfg!(target_os = "macos") && modifiers.contains(keyboard_types::Modifiers::META)),
-        mac_cmd: cfg!(target_os = "macos") && modifiers.contains(keyboard_types::Modifiers::META),
-        shift: modifiers.contains(keyboard_types::Modifiers::SHIFT),
+        let mut state = QueueState::new(Box::new(ManualClock::new()));
+        let payload = DragPayload::files(vec!["/renders/take 1.wav".into()])
+            .with_data("text/plain", b"take 1".to_vec());
+
+        // Without a drag source the drag is dropped.
+        Queue::new(&mut state).start_drag(payload.clone());
+        assert!(!state.start_pending_drag(&NoWindow));
+
+        let drag_source = TestDragSource::default();
+        let mut queue = Queue::new(&mut state);
+        queue.set_drag_source(drag_source.clone());
+        queue.start_drag(payload.clone());
+        assert!(state.start_pending_drag(&NoWindow));
+        // Each drag is started once.
+        assert!(!state.start_pending_drag(&NoWindow));
+        assert_eq!(*lock(&drag_source.0), vec![payload]);
     }
 }